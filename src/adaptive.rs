@@ -0,0 +1,115 @@
+use crate::generator::{Difficulty, PracticeMode, ProblemKind};
+use std::time::Duration;
+
+/// Rating every kind of problem starts at; it maps to level 1.
+const START_RATING: f64 = 1000.0;
+/// Rating points between one level and the next.
+const RATING_PER_LEVEL: f64 = 100.0;
+/// How far a single answer can move a rating.
+const K_FACTOR: f64 = 64.0;
+/// Correct answers at least this fast count as a full win.
+const FAST_ANSWER: Duration = Duration::from_secs(3);
+/// Correct answers this slow or slower count as half a win.
+const SLOW_ANSWER: Duration = Duration::from_secs(9);
+
+/// Tracks an Elo-like rating per kind of problem and turns it into a
+/// [`Difficulty`].
+///
+/// Each answer is scored like a game result: a fast correct answer is a win
+/// (1.0), a slow correct one drifts towards a draw (0.5) and a wrong one is a
+/// loss (0.0). Problems are generated at the player's own rating, so the
+/// expected result is always a draw and the rating moves by
+/// `K_FACTOR * (result - 0.5)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveDifficulty {
+    ratings: [f64; ProblemKind::ALL.len()],
+}
+
+impl Default for AdaptiveDifficulty {
+    fn default() -> Self {
+        Self {
+            ratings: [START_RATING; ProblemKind::ALL.len()],
+        }
+    }
+}
+
+impl AdaptiveDifficulty {
+    pub fn record(&mut self, kind: ProblemKind, correct: bool, latency: Duration) {
+        let result = if !correct {
+            0.0
+        } else if latency <= FAST_ANSWER {
+            1.0
+        } else {
+            let slowness =
+                (latency - FAST_ANSWER).as_secs_f64() / (SLOW_ANSWER - FAST_ANSWER).as_secs_f64();
+            1.0 - 0.5 * slowness.min(1.0)
+        };
+
+        let max_rating = START_RATING + RATING_PER_LEVEL * (Difficulty::MAX_LEVEL - 1) as f64;
+        let rating = &mut self.ratings[kind.index()];
+        *rating = (*rating + K_FACTOR * (result - 0.5)).clamp(START_RATING, max_rating);
+    }
+
+    pub fn rating(&self, kind: ProblemKind) -> f64 {
+        self.ratings[kind.index()]
+    }
+
+    /// Level for one kind of problem, from 1 to [`Difficulty::MAX_LEVEL`].
+    pub fn kind_level(&self, kind: ProblemKind) -> u32 {
+        level_for(self.rating(kind))
+    }
+
+    /// Overall level in `mode`, from the average rating of the kinds it is
+    /// rated on. Kinds the mode never asks would hold the level back.
+    pub fn level(&self, mode: PracticeMode) -> u32 {
+        let kinds = mode.rated_kinds();
+        let total: f64 = kinds.iter().map(|&kind| self.rating(kind)).sum();
+        level_for(total / kinds.len() as f64)
+    }
+
+    pub fn difficulty(&self, mode: PracticeMode) -> Difficulty {
+        ProblemKind::ALL.into_iter().fold(
+            Difficulty::at_level(self.level(mode)),
+            |difficulty, kind| difficulty.with_kind_level(kind, self.kind_level(kind)),
+        )
+    }
+}
+
+fn level_for(rating: f64) -> u32 {
+    let level = 1 + ((rating - START_RATING) / RATING_PER_LEVEL).floor() as u32;
+    level.min(Difficulty::MAX_LEVEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_correct_answers_raise_only_that_operation() {
+        let mut adaptive = AdaptiveDifficulty::default();
+        for _ in 0..7 {
+            adaptive.record(ProblemKind::Addition, true, Duration::from_secs(1));
+        }
+        assert_eq!(adaptive.kind_level(ProblemKind::Addition), 3);
+        assert_eq!(adaptive.kind_level(ProblemKind::Division), 1);
+
+        let difficulty = adaptive.difficulty(PracticeMode::Mixed);
+        assert!(difficulty.max_operand(ProblemKind::Addition) > 10);
+        assert_eq!(difficulty.max_operand(ProblemKind::Division), 10);
+        assert_eq!(adaptive.level(PracticeMode::Addition), 3);
+        assert_eq!(adaptive.level(PracticeMode::Mixed), 1);
+    }
+
+    #[test]
+    fn slow_correct_answers_barely_move_the_rating() {
+        let mut adaptive = AdaptiveDifficulty::default();
+        for _ in 0..20 {
+            adaptive.record(ProblemKind::Multiplication, true, Duration::from_secs(20));
+        }
+        assert_eq!(adaptive.kind_level(ProblemKind::Multiplication), 1);
+
+        adaptive.record(ProblemKind::Multiplication, true, Duration::from_secs(1));
+        adaptive.record(ProblemKind::Multiplication, false, Duration::from_secs(1));
+        assert_eq!(adaptive.rating(ProblemKind::Multiplication), START_RATING);
+    }
+}
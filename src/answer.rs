@@ -0,0 +1,265 @@
+use crate::rational::{Rational, WrittenDecimal, WrittenFraction};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How fraction answers must be written to count as correct.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FractionStrictness {
+    /// Any equal value counts, so `6/8` answers `3/4`.
+    #[default]
+    Equivalent,
+    /// The answer must be in lowest terms, as a proper mixed number or an
+    /// improper fraction.
+    Simplified,
+}
+
+impl FractionStrictness {
+    pub const ALL: [FractionStrictness; 2] = [
+        FractionStrictness::Equivalent,
+        FractionStrictness::Simplified,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FractionStrictness::Equivalent => "Any equivalent form",
+            FractionStrictness::Simplified => "Lowest terms only",
+        }
+    }
+}
+
+/// Points for an estimate within `within_percent` of the exact value.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EstimationBand {
+    pub within_percent: f64,
+    pub points: i32,
+}
+
+/// The configured rules for accepting non-integer answers.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AnswerRules {
+    pub fraction_strictness: FractionStrictness,
+    /// Largest accepted distance from a decimal answer.
+    pub decimal_tolerance: f64,
+    /// Decimal answers must be written with the problem's number of places,
+    /// so `2.50` rather than `2.5`.
+    pub require_decimal_places: bool,
+}
+
+/// The expected answer to a problem.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Answer {
+    Integer(i32),
+    Fraction {
+        value: Rational,
+        /// Set for "simplify" problems, where lowest terms is the point of
+        /// the question regardless of the configured strictness.
+        must_simplify: bool,
+    },
+    /// A decimal shown with a fixed number of places. Percentages use this
+    /// too, written without the `%`.
+    Decimal {
+        value: Rational,
+        places: u32,
+    },
+    /// The exact result of an estimation problem. Guesses are scored by how
+    /// close they come, see [`Answer::percent_off`].
+    Estimate {
+        exact: i32,
+    },
+}
+
+impl Answer {
+    pub fn value(&self) -> Rational {
+        match self {
+            Answer::Integer(n) => Rational::from(*n),
+            Answer::Fraction { value, .. } => *value,
+            Answer::Decimal { value, .. } => *value,
+            Answer::Estimate { exact } => Rational::from(*exact),
+        }
+    }
+
+    /// How far `input` is from the exact value, as a percentage of it.
+    /// Returns `None` if the input is not a number.
+    pub fn percent_off(&self, input: &str) -> Option<f64> {
+        let written: WrittenDecimal = normalize_sign(input).parse().ok()?;
+        let exact = self.value().to_f64();
+        let off = (written.value.to_f64() - exact).abs();
+        Some(if exact == 0.0 {
+            if off == 0.0 {
+                0.0
+            } else {
+                f64::INFINITY
+            }
+        } else {
+            off / exact.abs() * 100.0
+        })
+    }
+
+    /// Checks the player's input. Returns `None` if the input is not a
+    /// well-formed answer of the right kind at all.
+    pub fn check(&self, input: &str, rules: &AnswerRules) -> Option<bool> {
+        let input = &normalize_sign(input);
+        match self {
+            Answer::Integer(expected) => input.trim().parse::<i32>().ok().map(|n| n == *expected),
+            Answer::Fraction {
+                value,
+                must_simplify,
+            } => {
+                let written: WrittenFraction = input.parse().ok()?;
+                let simplified_enough = written.in_lowest_terms
+                    || (!must_simplify
+                        && rules.fraction_strictness == FractionStrictness::Equivalent);
+                Some(written.value == *value && simplified_enough)
+            }
+            Answer::Decimal { value, places } => {
+                // A trailing % is fine for percentage answers
+                let input = input.trim();
+                let input = input.strip_suffix('%').unwrap_or(input);
+                let written: WrittenDecimal = input.parse().ok()?;
+                if rules.require_decimal_places && written.places != *places {
+                    return Some(false);
+                }
+                // The small slack keeps e.g. 2.51 within 0.01 of 2.5 despite
+                // floating-point rounding
+                let distance = (written.value.to_f64() - value.to_f64()).abs();
+                let close_enough =
+                    written.value == *value || distance <= rules.decimal_tolerance + 1e-9;
+                Some(close_enough)
+            }
+            Answer::Estimate { .. } => self.percent_off(input).map(|off| off == 0.0),
+        }
+    }
+}
+
+impl FromStr for Answer {
+    type Err = ();
+
+    /// Reads an answer written the way a player would type it: `12`, `-3`,
+    /// `3/4`, `1 1/2` or `2.50`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = normalize_sign(s);
+        if let Ok(n) = s.parse() {
+            return Ok(Answer::Integer(n));
+        }
+        if s.contains('.') {
+            let written: WrittenDecimal = s.parse()?;
+            return Ok(Answer::Decimal {
+                value: written.value,
+                places: written.places,
+            });
+        }
+        let written: WrittenFraction = s.parse()?;
+        Ok(Answer::Fraction {
+            value: written.value,
+            must_simplify: false,
+        })
+    }
+}
+
+/// Accepts the ways people write a negative number: a typographic minus,
+/// a space after the sign, or the `(-7)` form questions use.
+fn normalize_sign(input: &str) -> String {
+    let mut input = input.trim();
+    if let Some(inner) = input
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        input = inner.trim();
+    }
+    let input = input.replacen('\u{2212}', "-", 1);
+    match input.strip_prefix('-') {
+        Some(rest) => format!("-{}", rest.trim_start()),
+        None => input,
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Integer(n) => write!(f, "{}", n),
+            Answer::Fraction { value, .. } => write!(f, "{}", value),
+            Answer::Decimal { value, places } => write!(f, "{}", value.to_decimal_string(*places)),
+            Answer::Estimate { exact } => write!(f, "{}", exact),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fraction_answers_follow_the_strictness() {
+        let answer = Answer::Fraction {
+            value: Rational::new(3, 2).unwrap(),
+            must_simplify: false,
+        };
+        let equivalent = AnswerRules::default();
+        let simplified = AnswerRules {
+            fraction_strictness: FractionStrictness::Simplified,
+            ..AnswerRules::default()
+        };
+        for input in ["3/2", "1 1/2", "6/4"] {
+            assert_eq!(answer.check(input, &equivalent), Some(true));
+        }
+        assert_eq!(answer.check("6/4", &simplified), Some(false));
+        assert_eq!(answer.check("1 1/2", &simplified), Some(true));
+        assert_eq!(answer.check("-3/2", &equivalent), Some(false));
+        assert_eq!(answer.check("x", &equivalent), None);
+    }
+
+    #[test]
+    fn negative_answers_accept_common_spellings() {
+        let answer = Answer::Integer(-4);
+        let rules = AnswerRules::default();
+        for input in ["-4", " - 4", "(-4)", "\u{2212}4"] {
+            assert_eq!(answer.check(input, &rules), Some(true), "{:?}", input);
+        }
+        assert_eq!(answer.check("4", &rules), Some(false));
+        assert_eq!(answer.check("--4", &rules), None);
+    }
+
+    #[test]
+    fn answers_parse_from_text() {
+        assert_eq!("-12".parse(), Ok(Answer::Integer(-12)));
+        assert_eq!(
+            "2.50".parse::<Answer>().map(|answer| answer.to_string()),
+            Ok("2.50".to_string())
+        );
+        assert_eq!(
+            "1 1/2".parse::<Answer>().map(|answer| answer.value()),
+            Ok(Rational::new(3, 2).unwrap())
+        );
+        assert!("twelve".parse::<Answer>().is_err());
+    }
+
+    #[test]
+    fn decimal_answers_follow_tolerance_and_places() {
+        let answer = Answer::Decimal {
+            value: Rational::new(5, 2).unwrap(),
+            places: 2,
+        };
+        let exact = AnswerRules::default();
+        assert_eq!(answer.check("2.5", &exact), Some(true));
+        assert_eq!(answer.check("2.5%", &exact), Some(true));
+        assert_eq!(answer.check("2.51", &exact), Some(false));
+
+        let tolerant = AnswerRules {
+            decimal_tolerance: 0.05,
+            ..AnswerRules::default()
+        };
+        assert_eq!(answer.check("2.54", &tolerant), Some(true));
+
+        let strict_places = AnswerRules {
+            require_decimal_places: true,
+            ..AnswerRules::default()
+        };
+        assert_eq!(answer.check("2.5", &strict_places), Some(false));
+        assert_eq!(answer.check("2.50", &strict_places), Some(true));
+        assert_eq!(answer.to_string(), "2.50");
+    }
+}
@@ -0,0 +1,196 @@
+use crate::answer::{Answer, EstimationBand};
+use crate::expr::{Expr, Op};
+use crate::generator::Problem;
+use crate::rational::{Rational, WrittenDecimal};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// How many options a multiple-choice question shows.
+pub const CHOICE_COUNT: usize = 4;
+
+/// The correct answer and three plausible wrong ones, shuffled.
+///
+/// Wrong answers come from common mistakes where possible: using the wrong
+/// operation, swapping two digits, or being off by one. Small offsets fill
+/// in when those don't give enough distinct values. Wrong estimates are
+/// kept outside every one of `bands`, so they never earn points.
+pub fn choices(problem: &Problem, bands: &[EstimationBand], rng: &mut impl Rng) -> Vec<Answer> {
+    let widest = bands
+        .iter()
+        .map(|band| band.within_percent)
+        .fold(0.0, f64::max);
+    let correct = problem.answer.value();
+    let mut candidates = wrong_operation(problem);
+    candidates.extend(digit_swap(&problem.answer));
+    candidates.shuffle(rng);
+
+    let step = step(&problem.answer, widest);
+    let offsets = [1, -1, 2, -2, 10, -10, 3, -3];
+    candidates.extend(
+        offsets
+            .iter()
+            .filter_map(|&offset| correct.checked_add(step.checked_mul(offset.into())?)),
+    );
+
+    let mut values = vec![correct];
+    for candidate in candidates {
+        if values.len() == CHOICE_COUNT {
+            break;
+        }
+        if !values.contains(&candidate) && fits(&problem.answer, candidate, widest) {
+            values.push(candidate);
+        }
+    }
+    values.shuffle(rng);
+    values
+        .into_iter()
+        .map(|value| like(&problem.answer, value))
+        .collect()
+}
+
+/// The question's last operation swapped for each of the others, e.g.
+/// `7 + 5` for `7 * 5`.
+fn wrong_operation(problem: &Problem) -> Vec<Rational> {
+    let Ok(Expr::Binary { op, lhs, rhs }) = problem.question.parse::<Expr>() else {
+        return Vec::new();
+    };
+    [Op::Add, Op::Sub, Op::Mul, Op::Div]
+        .into_iter()
+        .filter(|&other| other != op)
+        .filter_map(|other| {
+            Expr::binary(other, (*lhs).clone(), (*rhs).clone())
+                .eval_exact()
+                .ok()
+        })
+        .collect()
+}
+
+/// The answer with its last two digits swapped, e.g. `24` for `42`. A
+/// fraction is flipped over instead.
+fn digit_swap(answer: &Answer) -> Option<Rational> {
+    if let Answer::Fraction { value, .. } = answer {
+        return Rational::new(value.denom(), value.numer());
+    }
+    let mut text: Vec<char> = answer.to_string().chars().collect();
+    let digits: Vec<usize> = (0..text.len())
+        .filter(|&i| text[i].is_ascii_digit())
+        .collect();
+    let [.., first, second] = digits[..] else {
+        return None;
+    };
+    text.swap(first, second);
+    let swapped: String = text.into_iter().collect();
+    let value = match answer {
+        Answer::Decimal { .. } => swapped.parse::<WrittenDecimal>().ok()?.value,
+        _ => swapped.parse().ok()?,
+    };
+    Some(value)
+}
+
+/// The smallest natural difference for an answer: 1, one over the
+/// denominator, one in the last decimal place, or just over `widest`
+/// percent of an estimate.
+fn step(answer: &Answer, widest: f64) -> Rational {
+    match answer {
+        Answer::Integer(_) => Rational::integer(1),
+        Answer::Fraction { value, .. } => {
+            Rational::new(1, value.denom()).expect("denominators are positive")
+        }
+        Answer::Decimal { places, .. } => {
+            Rational::new(1, 10i64.pow(*places)).expect("powers of ten are positive")
+        }
+        // Every wrong option misses the widest scoring band
+        Answer::Estimate { exact } => {
+            Rational::integer((f64::from(*exact).abs() * widest / 100.0).ceil() as i64 + 1)
+        }
+    }
+}
+
+/// Whether `value` can be shown the same way as `answer`, and as an
+/// estimate is more than `widest` percent off.
+fn fits(answer: &Answer, value: Rational, widest: f64) -> bool {
+    match answer {
+        Answer::Integer(_) => value.is_integer() && i32::try_from(value.numer()).is_ok(),
+        Answer::Estimate { .. } => {
+            value.is_integer()
+                && i32::try_from(value.numer()).is_ok()
+                && answer
+                    .percent_off(&value.to_string())
+                    .is_some_and(|off| off > widest)
+        }
+        Answer::Fraction { .. } => true,
+        Answer::Decimal { places, .. } => value.decimal_places().is_some_and(|p| p <= *places),
+    }
+}
+
+fn like(answer: &Answer, value: Rational) -> Answer {
+    match answer {
+        Answer::Integer(_) => Answer::Integer(value.numer() as i32),
+        Answer::Fraction { must_simplify, .. } => Answer::Fraction {
+            value,
+            must_simplify: *must_simplify,
+        },
+        Answer::Decimal { places, .. } => Answer::Decimal {
+            value,
+            places: *places,
+        },
+        Answer::Estimate { .. } => Answer::Estimate {
+            exact: value.numer() as i32,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::generator::ProblemKind;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn choices_include_the_answer_and_common_mistakes() {
+        let problem = Problem {
+            question: "7 * 6".to_string(),
+            answer: Answer::Integer(42),
+            kind: ProblemKind::Multiplication,
+            operations: 1,
+            points: None,
+        };
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let choices = choices(&problem, &[], &mut rng);
+            assert_eq!(choices.len(), CHOICE_COUNT);
+            assert!(choices.contains(&Answer::Integer(42)));
+            for (i, choice) in choices.iter().enumerate() {
+                assert!(!choices[..i].contains(choice));
+            }
+        }
+
+        let mistakes = wrong_operation(&problem);
+        assert!(mistakes.contains(&Rational::integer(13)));
+        assert_eq!(digit_swap(&problem.answer), Some(Rational::integer(24)));
+    }
+
+    #[test]
+    fn wrong_estimates_earn_no_points() {
+        let config = GameConfig::default();
+        let problem = Problem {
+            question: "487 * 21 ≈ ?".to_string(),
+            answer: Answer::Estimate { exact: 10227 },
+            kind: ProblemKind::Estimation,
+            operations: 1,
+            points: None,
+        };
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let choices = choices(&problem, &config.estimation_bands, &mut rng);
+            assert_eq!(choices.len(), CHOICE_COUNT);
+            let scoring = choices.iter().filter(|choice| {
+                let off = problem.answer.percent_off(&choice.to_string()).unwrap();
+                config.estimation_points(off) > 0
+            });
+            assert_eq!(scoring.count(), 1, "{:?}", choices);
+        }
+    }
+}
@@ -0,0 +1,507 @@
+use crate::answer::{AnswerRules, EstimationBand, FractionStrictness};
+use crate::generator::{Difficulty, ProblemGenerator};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The tunable rules of the game, loaded from a TOML file.
+///
+/// Every field is optional in the file; missing ones keep their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    /// Seconds on the clock when the game starts.
+    pub starting_secs: u64,
+    /// Seconds added for a correct answer.
+    pub correct_bonus_secs: u64,
+    /// Seconds taken away for a wrong or invalid answer.
+    pub wrong_penalty_secs: u64,
+    /// Correct answers needed to finish a sprint.
+    pub sprint_questions: u32,
+    /// Wrong answers a survival game allows before it ends.
+    pub survival_lives: u32,
+    /// Questions answered per level in survival, where difficulty climbs
+    /// steadily instead of following the score.
+    pub survival_level_every: u32,
+    /// Give every question its own countdown on top of the game's rules. A
+    /// question that runs out counts as a wrong answer.
+    pub question_timer: bool,
+    /// Seconds for each question while the player has no streak.
+    pub question_secs: f64,
+    /// Seconds taken off the question countdown per correct answer in a row.
+    pub question_streak_shrink_secs: f64,
+    /// The question countdown never drops below this.
+    pub question_min_secs: f64,
+    /// Answers given within this many seconds earn the speed bonus.
+    pub speed_bonus_secs: f64,
+    /// Points added to a fast correct answer; 0 turns the bonus off.
+    pub speed_bonus_points: i32,
+    /// Points for each operation in a correct PEMDAS answer; other answers
+    /// are worth 1.
+    pub pemdas_points: i32,
+    /// How many levels of operations PEMDAS problems nest.
+    pub pemdas_depth: u32,
+    /// Whether fraction answers must be in lowest terms.
+    pub fraction_strictness: FractionStrictness,
+    /// How far off a decimal answer may be and still count.
+    pub decimal_tolerance: f64,
+    /// Decimal answers must show every decimal place, e.g. `2.50`.
+    pub require_decimal_places: bool,
+    /// Pick from four answers instead of typing one.
+    pub multiple_choice: bool,
+    /// Adjust difficulty from accuracy and speed instead of the score
+    /// thresholds below.
+    pub adaptive: bool,
+    /// Score at which problems move from easy to medium.
+    pub medium_score: i32,
+    /// Score at which problems move from medium to hard.
+    pub hard_score: i32,
+    /// Seed for the problem sequence; a random one is used when unset. A
+    /// fixed seed gives players the same problems as long as they answer
+    /// the same questions right, since adaptive difficulty then ignores
+    /// how long answers take.
+    pub seed: Option<u64>,
+    /// Points for an estimate, by how close it is. The first band the
+    /// estimate falls in counts; outside all of them it is wrong.
+    pub estimation_bands: Vec<EstimationBand>,
+    /// Score multipliers for correct answers in a row. The last tier the
+    /// streak has reached counts; below all of them points are not
+    /// multiplied.
+    pub streak_tiers: Vec<StreakTier>,
+}
+
+/// Points are multiplied by `multiplier` once `streak` answers in a row
+/// were correct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreakTier {
+    pub streak: u32,
+    pub multiplier: i32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            starting_secs: 30,
+            correct_bonus_secs: 1,
+            wrong_penalty_secs: 2,
+            sprint_questions: 20,
+            survival_lives: 3,
+            survival_level_every: 5,
+            question_timer: false,
+            question_secs: 5.0,
+            question_streak_shrink_secs: 0.25,
+            question_min_secs: 2.0,
+            speed_bonus_secs: 2.0,
+            speed_bonus_points: 1,
+            pemdas_points: 1,
+            pemdas_depth: ProblemGenerator::DEFAULT_PEMDAS_DEPTH,
+            fraction_strictness: FractionStrictness::default(),
+            decimal_tolerance: 0.0,
+            require_decimal_places: false,
+            multiple_choice: false,
+            adaptive: true,
+            medium_score: 5,
+            hard_score: 10,
+            seed: None,
+            estimation_bands: vec![
+                EstimationBand {
+                    within_percent: 5.0,
+                    points: 3,
+                },
+                EstimationBand {
+                    within_percent: 10.0,
+                    points: 2,
+                },
+                EstimationBand {
+                    within_percent: 20.0,
+                    points: 1,
+                },
+            ],
+            streak_tiers: vec![
+                StreakTier {
+                    streak: 5,
+                    multiplier: 2,
+                },
+                StreakTier {
+                    streak: 10,
+                    multiplier: 3,
+                },
+            ],
+        }
+    }
+}
+
+/// Why a settings file could not be used.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ConfigError::Parse { path, source } => {
+                write!(
+                    f,
+                    "{} is not a valid settings file: {}",
+                    path.display(),
+                    source
+                )
+            }
+            ConfigError::Invalid(message) => write!(f, "invalid settings: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            ConfigError::Parse { source, .. } => Some(source),
+            ConfigError::Invalid(_) => None,
+        }
+    }
+}
+
+impl GameConfig {
+    pub const MAX_PEMDAS_DEPTH: u32 = 4;
+    /// Upper bound for every setting given in seconds, so clocks and
+    /// penalties always fit in a [`Duration`].
+    pub const MAX_SECS: f64 = 3600.0;
+
+    /// `<platform config dir>/rapid_math/config.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rapid_math").join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let config: Self = toml::from_str(&text).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Like [`GameConfig::load`], but a missing file yields the defaults.
+    pub fn load_or_default(path: &Path) -> Result<Self, ConfigError> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        self.validate()?;
+        let io_error = |source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        let text = toml::to_string_pretty(self).expect("settings always serialize");
+        fs::write(path, text).map_err(io_error)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let max_secs = Self::MAX_SECS as u64;
+        if !(1..=max_secs).contains(&self.starting_secs) {
+            return Err(ConfigError::Invalid(format!(
+                "starting_secs must be between 1 and {}",
+                max_secs
+            )));
+        }
+        if self.correct_bonus_secs > max_secs || self.wrong_penalty_secs > max_secs {
+            return Err(ConfigError::Invalid(format!(
+                "correct_bonus_secs and wrong_penalty_secs must be at most {}",
+                max_secs
+            )));
+        }
+        if self.sprint_questions == 0 {
+            return Err(ConfigError::Invalid(
+                "sprint_questions must be at least 1".to_string(),
+            ));
+        }
+        if self.survival_lives == 0 {
+            return Err(ConfigError::Invalid(
+                "survival_lives must be at least 1".to_string(),
+            ));
+        }
+        if self.survival_level_every == 0 {
+            return Err(ConfigError::Invalid(
+                "survival_level_every must be at least 1".to_string(),
+            ));
+        }
+        if !(self.question_secs > 0.0 && self.question_secs <= Self::MAX_SECS) {
+            return Err(ConfigError::Invalid(format!(
+                "question_secs must be positive and at most {}",
+                Self::MAX_SECS
+            )));
+        }
+        if !(self.question_min_secs > 0.0 && self.question_min_secs <= self.question_secs) {
+            return Err(ConfigError::Invalid(
+                "question_min_secs must be positive and at most question_secs".to_string(),
+            ));
+        }
+        if !(0.0..=Self::MAX_SECS).contains(&self.question_streak_shrink_secs) {
+            return Err(ConfigError::Invalid(format!(
+                "question_streak_shrink_secs must be between 0 and {}",
+                Self::MAX_SECS
+            )));
+        }
+        if !(0.0..=Self::MAX_SECS).contains(&self.speed_bonus_secs) {
+            return Err(ConfigError::Invalid(format!(
+                "speed_bonus_secs must be between 0 and {}",
+                Self::MAX_SECS
+            )));
+        }
+        if self.speed_bonus_points < 0 {
+            return Err(ConfigError::Invalid(
+                "speed_bonus_points must not be negative".to_string(),
+            ));
+        }
+        if self.pemdas_points < 1 {
+            return Err(ConfigError::Invalid(
+                "pemdas_points must be at least 1".to_string(),
+            ));
+        }
+        if !(2..=Self::MAX_PEMDAS_DEPTH).contains(&self.pemdas_depth) {
+            return Err(ConfigError::Invalid(format!(
+                "pemdas_depth must be between 2 and {}",
+                Self::MAX_PEMDAS_DEPTH
+            )));
+        }
+        if self.decimal_tolerance.is_nan() || self.decimal_tolerance < 0.0 {
+            return Err(ConfigError::Invalid(
+                "decimal_tolerance must be zero or positive".to_string(),
+            ));
+        }
+        if self.estimation_bands.is_empty() {
+            return Err(ConfigError::Invalid(
+                "estimation_bands must have at least one band".to_string(),
+            ));
+        }
+        for pair in self.estimation_bands.windows(2) {
+            if pair[1].within_percent <= pair[0].within_percent {
+                return Err(ConfigError::Invalid(
+                    "estimation_bands must be ordered from closest to widest".to_string(),
+                ));
+            }
+        }
+        for band in &self.estimation_bands {
+            if band.within_percent.is_nan() || band.within_percent < 0.0 || band.points < 1 {
+                return Err(ConfigError::Invalid(
+                    "estimation bands need a non-negative percentage and at least 1 point"
+                        .to_string(),
+                ));
+            }
+        }
+        for pair in self.streak_tiers.windows(2) {
+            if pair[1].streak <= pair[0].streak {
+                return Err(ConfigError::Invalid(
+                    "streak_tiers must be ordered from shortest to longest streak".to_string(),
+                ));
+            }
+        }
+        if self
+            .streak_tiers
+            .iter()
+            .any(|tier| tier.streak < 1 || tier.multiplier < 1)
+        {
+            return Err(ConfigError::Invalid(
+                "streak tiers need a streak and a multiplier of at least 1".to_string(),
+            ));
+        }
+        if self.medium_score < 0 {
+            return Err(ConfigError::Invalid(
+                "medium_score must not be negative".to_string(),
+            ));
+        }
+        if self.hard_score < self.medium_score {
+            return Err(ConfigError::Invalid(format!(
+                "hard_score ({}) must not be below medium_score ({})",
+                self.hard_score, self.medium_score
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn answer_rules(&self) -> AnswerRules {
+        AnswerRules {
+            fraction_strictness: self.fraction_strictness,
+            decimal_tolerance: self.decimal_tolerance,
+            require_decimal_places: self.require_decimal_places,
+        }
+    }
+
+    /// Points for an estimate `percent_off` the exact value, or 0 if it is
+    /// outside every band.
+    pub fn estimation_points(&self, percent_off: f64) -> i32 {
+        self.estimation_bands
+            .iter()
+            .find(|band| percent_off <= band.within_percent)
+            .map_or(0, |band| band.points)
+    }
+
+    /// Multiplier for the next correct answer after `streak` in a row.
+    pub fn streak_multiplier(&self, streak: u32) -> i32 {
+        self.streak_tiers
+            .iter()
+            .rev()
+            .find(|tier| streak >= tier.streak)
+            .map_or(1, |tier| tier.multiplier)
+    }
+
+    /// Extra points for a correct answer given after `latency`.
+    pub fn speed_bonus(&self, latency: Duration) -> i32 {
+        if latency < Duration::from_secs_f64(self.speed_bonus_secs) {
+            self.speed_bonus_points
+        } else {
+            0
+        }
+    }
+
+    pub fn starting_time(&self) -> Duration {
+        Duration::from_secs(self.starting_secs)
+    }
+
+    pub fn correct_bonus(&self) -> Duration {
+        Duration::from_secs(self.correct_bonus_secs)
+    }
+
+    pub fn wrong_penalty(&self) -> Duration {
+        Duration::from_secs(self.wrong_penalty_secs)
+    }
+
+    /// Time for a question after `streak` correct answers in a row.
+    pub fn question_time(&self, streak: u32) -> Duration {
+        let secs = self.question_secs - self.question_streak_shrink_secs * f64::from(streak);
+        Duration::from_secs_f64(secs.max(self.question_min_secs))
+    }
+
+    /// Difficulty from the score thresholds, used when `adaptive` is off.
+    pub fn difficulty_for(&self, score: i32) -> Difficulty {
+        if score < self.medium_score {
+            Difficulty::EASY
+        } else if score < self.hard_score {
+            Difficulty::MEDIUM
+        } else {
+            Difficulty::HARD
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_files_keep_defaults() {
+        let config: GameConfig = toml::from_str("starting_secs = 60").unwrap();
+        assert_eq!(config.starting_secs, 60);
+        assert_eq!(config.pemdas_points, GameConfig::default().pemdas_points);
+    }
+
+    #[test]
+    fn unknown_keys_and_bad_values_are_rejected() {
+        assert!(toml::from_str::<GameConfig>("starting_time = 60").is_err());
+
+        let config = GameConfig {
+            medium_score: 10,
+            hard_score: 5,
+            ..GameConfig::default()
+        };
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+
+        for starting_secs in [0, u64::MAX] {
+            let config = GameConfig {
+                starting_secs,
+                ..GameConfig::default()
+            };
+            assert!(config.validate().is_err(), "{}", starting_secs);
+        }
+        let config = GameConfig {
+            correct_bonus_secs: u64::MAX,
+            ..GameConfig::default()
+        };
+        assert!(config.validate().is_err());
+
+        for speed_bonus_secs in [f64::NAN, f64::INFINITY, 1e30, -1.0] {
+            let config = GameConfig {
+                speed_bonus_secs,
+                ..GameConfig::default()
+            };
+            assert!(config.validate().is_err(), "{}", speed_bonus_secs);
+        }
+
+        for (question_secs, question_min_secs, question_streak_shrink_secs) in [
+            (1e300, 1.0, 0.25),
+            (f64::INFINITY, 1.0, 0.25),
+            (5.0, f64::NAN, 0.25),
+            (5.0, 2.0, f64::INFINITY),
+        ] {
+            let config = GameConfig {
+                question_secs,
+                question_min_secs,
+                question_streak_shrink_secs,
+                ..GameConfig::default()
+            };
+            assert!(config.validate().is_err(), "{:?}", config);
+        }
+    }
+
+    #[test]
+    fn estimation_bands_round_trip_and_score() {
+        let config = GameConfig::default();
+        let text = toml::to_string_pretty(&config).unwrap();
+        assert_eq!(toml::from_str::<GameConfig>(&text).unwrap(), config);
+
+        assert_eq!(config.estimation_points(0.0), 3);
+        assert_eq!(config.estimation_points(7.5), 2);
+        assert_eq!(config.estimation_points(20.0), 1);
+        assert_eq!(config.estimation_points(20.1), 0);
+    }
+
+    #[test]
+    fn streak_tiers_pick_the_longest_reached() {
+        let config = GameConfig::default();
+        assert_eq!(config.streak_multiplier(0), 1);
+        assert_eq!(config.streak_multiplier(4), 1);
+        assert_eq!(config.streak_multiplier(5), 2);
+        assert_eq!(config.streak_multiplier(25), 3);
+
+        let config = GameConfig {
+            streak_tiers: vec![
+                StreakTier {
+                    streak: 5,
+                    multiplier: 2,
+                },
+                StreakTier {
+                    streak: 3,
+                    multiplier: 3,
+                },
+            ],
+            ..config
+        };
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+    }
+}
@@ -0,0 +1,216 @@
+//! Screen for writing custom problem sets without leaving the game.
+
+use eframe::egui;
+use rapid_math::problem_set::{CustomProblem, ProblemSet};
+use std::path::{Path, PathBuf};
+
+/// What the editor asks of the app after a frame.
+pub enum EditorAction {
+    None,
+    /// Play this set now.
+    Play(ProblemSet),
+    Close,
+}
+
+/// One problem as typed, kept as text so half-finished edits survive.
+struct Row {
+    question: String,
+    answer: String,
+    points: i32,
+    /// Comma-separated.
+    tags: String,
+}
+
+impl Row {
+    fn blank() -> Self {
+        Self {
+            question: String::new(),
+            answer: String::new(),
+            points: 1,
+            tags: String::new(),
+        }
+    }
+
+    fn to_problem(&self) -> CustomProblem {
+        CustomProblem {
+            question: self.question.trim().to_string(),
+            answer: self.answer.trim().to_string(),
+            points: self.points,
+            tags: self
+                .tags
+                .split(',')
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl From<&CustomProblem> for Row {
+    fn from(problem: &CustomProblem) -> Self {
+        Self {
+            question: problem.question.clone(),
+            answer: problem.answer.clone(),
+            points: problem.points,
+            tags: problem.tags.join(", "),
+        }
+    }
+}
+
+pub struct ProblemSetEditor {
+    rows: Vec<Row>,
+    /// Where Save and Load go, as typed.
+    path: String,
+    /// Result of the last save or load.
+    status: Option<Result<String, String>>,
+}
+
+impl ProblemSetEditor {
+    /// Starts from `set` if there is one, else from a single blank problem.
+    pub fn new(set: Option<&ProblemSet>, path: Option<&Path>) -> Self {
+        let rows = match set {
+            Some(set) => set.problems.iter().map(Row::from).collect(),
+            None => vec![Row::blank()],
+        };
+        Self {
+            rows,
+            path: path
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            status: None,
+        }
+    }
+
+    fn to_set(&self) -> ProblemSet {
+        ProblemSet {
+            problems: self.rows.iter().map(Row::to_problem).collect(),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) -> EditorAction {
+        let mut action = EditorAction::None;
+        ui.vertical_centered(|ui| {
+            ui.heading("Problem Set");
+            ui.add_space(10.0);
+        });
+
+        let mut move_up = None;
+        let mut remove = None;
+        // Leave room for the buttons below; the grid is wider than the
+        // default window, so it scrolls both ways
+        egui::ScrollArea::both()
+            .max_height((ui.available_height() - 120.0).max(100.0))
+            .show(ui, |ui| {
+                egui::Grid::new("problem_set")
+                    .num_columns(6)
+                    .show(ui, |ui| {
+                        ui.label("Question");
+                        ui.label("Answer");
+                        ui.label("Points");
+                        ui.label("Tags");
+                        ui.label("");
+                        ui.label("");
+                        ui.end_row();
+
+                        let count = self.rows.len();
+                        for (index, row) in self.rows.iter_mut().enumerate() {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut row.question).desired_width(140.0),
+                            );
+                            ui.add(egui::TextEdit::singleline(&mut row.answer).desired_width(50.0));
+                            ui.add(egui::DragValue::new(&mut row.points).clamp_range(1..=100));
+                            ui.add(egui::TextEdit::singleline(&mut row.tags).desired_width(70.0));
+                            ui.horizontal(|ui| {
+                                if ui.add_enabled(index > 0, egui::Button::new("↑")).clicked() {
+                                    move_up = Some(index);
+                                }
+                                if ui
+                                    .add_enabled(index + 1 < count, egui::Button::new("↓"))
+                                    .clicked()
+                                {
+                                    move_up = Some(index + 1);
+                                }
+                                if ui.add_enabled(count > 1, egui::Button::new("✕")).clicked() {
+                                    remove = Some(index);
+                                }
+                            });
+                            // Arithmetic questions are checked against their answer;
+                            // anything else is marked so it can be checked by hand
+                            let problem = row.to_problem();
+                            match problem.validate() {
+                                Ok(()) if problem.expression().is_some() => ui.label("✔ checked"),
+                                Ok(()) => ui.weak("not checked (free text)"),
+                                Err(message) => ui.colored_label(egui::Color32::RED, message),
+                            };
+                            ui.end_row();
+                        }
+                    });
+                if ui.button("Add problem").clicked() {
+                    self.rows.push(Row::blank());
+                }
+            });
+        if let Some(index) = move_up {
+            self.rows.swap(index - 1, index);
+        }
+        if let Some(index) = remove {
+            self.rows.remove(index);
+        }
+
+        let set = self.to_set();
+        let valid = set.validate().is_ok();
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label("File");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.path)
+                    .hint_text("problems.toml, .json or .csv"),
+            );
+            if ui.add_enabled(valid, egui::Button::new("Save")).clicked() {
+                self.status = Some(self.save(&set));
+            }
+            if ui.button("Load").clicked() {
+                self.status = Some(self.load());
+            }
+        });
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(message)) => {
+                ui.colored_label(egui::Color32::RED, message);
+            }
+            None => {}
+        }
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            if ui.add_enabled(valid, egui::Button::new("Play")).clicked() {
+                action = EditorAction::Play(set);
+            }
+            if ui.button("Close").clicked() {
+                action = EditorAction::Close;
+            }
+        });
+        action
+    }
+
+    fn file(&self) -> Result<PathBuf, String> {
+        match self.path.trim() {
+            "" => Err("Enter a file name first".to_string()),
+            path => Ok(PathBuf::from(path)),
+        }
+    }
+
+    fn save(&self, set: &ProblemSet) -> Result<String, String> {
+        let path = self.file()?;
+        set.save(&path).map_err(|err| err.to_string())?;
+        Ok(format!("Saved to {}", path.display()))
+    }
+
+    fn load(&mut self) -> Result<String, String> {
+        let path = self.file()?;
+        let set = ProblemSet::load(&path).map_err(|err| err.to_string())?;
+        self.rows = set.problems.iter().map(Row::from).collect();
+        Ok(format!("Loaded {}", path.display()))
+    }
+}
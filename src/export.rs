@@ -0,0 +1,302 @@
+//! Writing a finished session to disk for analysis in other tools.
+//!
+//! # Schema, version 4
+//!
+//! JSON files contain a single object:
+//!
+//! | field             | type   | meaning                                        |
+//! |-------------------|--------|------------------------------------------------|
+//! | `schema_version`  | int    | always `4` for this layout                     |
+//! | `exported_at`     | string | RFC 3339 timestamp of the export               |
+//! | `mode`            | string | practice mode, e.g. `mixed`, `times-tables`    |
+//! | `format`          | string | `timed`, `sprint` or `survival`                |
+//! | `seed`            | int    | seed that reproduces the question sequence     |
+//! | `config`          | object | the [`GameConfig`] the game was played with    |
+//! | `score`           | int    | final score                                    |
+//! | `correct_answers` | int    | number of correct answers                      |
+//! | `wrong_answers`   | int    | number of wrong or invalid answers             |
+//! | `finish_time_ms`  | int    | sprint time with penalties; `null` otherwise   |
+//! | `questions`       | array  | one object per answer, see below               |
+//!
+//! Each question has `index` (0-based), `question`, `expected` (text, e.g.
+//! `12`, `3/4` or `2.50`), `given` (the raw input), `correct` (bool), `latency_ms`
+//! and `kind` (`addition`, `subtraction`, `multiplication`, `division`,
+//! `pemdas`, `fraction`, `decimal`, `percentage`, `integer`, `algebra`,
+//! `estimation` or `custom`). For estimation questions `expected` is the
+//! exact value and `correct` is set when the estimate earned any points.
+//!
+//! Version 1 stored `expected` as an integer and had no `fraction` kind.
+//! Version 2 had no `format` or `finish_time_ms`. Version 3 and earlier
+//! did not order config columns consistently.
+//!
+//! CSV files have one row per question with the question fields as columns,
+//! preceded by `schema_version`, `mode`, `format`, `seed`, `finish_time_ms`
+//! and one column per [`GameConfig`] field (in alphabetical order) so every
+//! row is self-contained.
+//!
+//! New settings add keys to `config` and config columns to CSV files
+//! without a version bump, so read config columns by their header name
+//! rather than by position. Every other field and column is fixed within a
+//! version.
+
+use crate::config::GameConfig;
+use crate::generator::ProblemKind;
+use crate::session::{GameFormat, GameSession};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever the layout of exported files changes.
+pub const SCHEMA_VERSION: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// Picks the format from the file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// A finished session in the exported layout.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionExport {
+    pub schema_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub mode: String,
+    pub format: String,
+    pub seed: u64,
+    pub config: GameConfig,
+    pub score: i32,
+    pub correct_answers: i32,
+    pub wrong_answers: i32,
+    pub finish_time_ms: Option<u128>,
+    pub questions: Vec<ExportedQuestion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedQuestion {
+    pub index: usize,
+    pub question: String,
+    pub expected: String,
+    pub given: String,
+    pub correct: bool,
+    pub latency_ms: u128,
+    pub kind: ProblemKind,
+}
+
+impl SessionExport {
+    pub fn from_session(session: &GameSession) -> Self {
+        let questions = session
+            .history()
+            .iter()
+            .enumerate()
+            .map(|(index, record)| ExportedQuestion {
+                index,
+                question: record.question.clone(),
+                expected: record.expected.to_string(),
+                given: record.given.clone(),
+                correct: record.correct,
+                latency_ms: record.latency.as_millis(),
+                kind: record.kind,
+            })
+            .collect();
+        Self {
+            schema_version: SCHEMA_VERSION,
+            exported_at: Utc::now(),
+            mode: session.mode().name().to_string(),
+            format: session.format().name().to_string(),
+            seed: session.seed(),
+            config: session.config().clone(),
+            score: session.score(),
+            correct_answers: session.correct_answers(),
+            wrong_answers: session.wrong_answers(),
+            finish_time_ms: (session.format() == GameFormat::Sprint)
+                .then(|| session.finish_time().as_millis()),
+            questions,
+        }
+    }
+
+    /// `<platform data dir>/rapid_math/exports/session-<timestamp>.<ext>`
+    pub fn default_path(&self, format: ExportFormat) -> Option<PathBuf> {
+        let name = format!(
+            "session-{}.{}",
+            self.exported_at.format("%Y%m%d-%H%M%S"),
+            format.extension()
+        );
+        dirs::data_dir().map(|dir| dir.join("rapid_math").join("exports").join(name))
+    }
+
+    pub fn write(&self, path: &Path, format: ExportFormat) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = File::create(path)?;
+        match format {
+            ExportFormat::Json => serde_json::to_writer_pretty(file, self).map_err(io::Error::from),
+            ExportFormat::Csv => self.write_csv(file),
+        }
+    }
+
+    fn write_csv(&self, file: File) -> io::Result<()> {
+        // Config columns come straight from the serialized `GameConfig`, so
+        // new settings show up without touching this function. The config's
+        // own `seed` is skipped in favour of the seed that was actually used.
+        let config = match serde_json::to_value(&self.config)? {
+            serde_json::Value::Object(config) => config,
+            _ => unreachable!("GameConfig serializes to an object"),
+        };
+        let config: Vec<(String, String)> = config
+            .into_iter()
+            .filter(|(key, _)| key != "seed")
+            .map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::Null => String::new(),
+                    serde_json::Value::String(value) => value,
+                    value => value.to_string(),
+                };
+                (key, value)
+            })
+            .collect();
+
+        let mut writer = csv::Writer::from_writer(file);
+        let header = ["schema_version", "mode", "format", "seed", "finish_time_ms"]
+            .into_iter()
+            .chain(config.iter().map(|(key, _)| key.as_str()))
+            .chain([
+                "index",
+                "question",
+                "expected",
+                "given",
+                "correct",
+                "latency_ms",
+                "kind",
+            ]);
+        writer.write_record(header)?;
+
+        let session_fields: Vec<String> = [
+            self.schema_version.to_string(),
+            self.mode.clone(),
+            self.format.clone(),
+            self.seed.to_string(),
+            self.finish_time_ms
+                .map(|time| time.to_string())
+                .unwrap_or_default(),
+        ]
+        .into_iter()
+        .chain(config.into_iter().map(|(_, value)| value))
+        .collect();
+        for question in &self.questions {
+            let question_fields = [
+                question.index.to_string(),
+                question.question.clone(),
+                question.expected.clone(),
+                question.given.clone(),
+                question.correct.to_string(),
+                question.latency_ms.to_string(),
+                question.kind.name().to_string(),
+            ];
+            writer.write_record(session_fields.iter().chain(&question_fields))?;
+        }
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{PracticeMode, ProblemGenerator};
+    use std::time::{Duration, Instant};
+
+    fn finished_session() -> GameSession {
+        let generator = ProblemGenerator::new(7).with_mode(PracticeMode::Addition);
+        let mut session = GameSession::new(GameConfig::default(), generator);
+        let now = Instant::now();
+        session.start(now);
+        session.tick(now + Duration::from_secs(2));
+        let answer = session.problem().answer.to_string();
+        session.submit(&answer);
+        session.submit("nope");
+        session
+    }
+
+    #[test]
+    fn csv_and_json_files_follow_the_schema() {
+        let export = SessionExport::from_session(&finished_session());
+        let dir = std::env::temp_dir().join(format!("rapid_math-export-{}", std::process::id()));
+
+        let path = dir.join("session.csv");
+        export.write(&path, ExportFormat::Csv).unwrap();
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        let header = reader.headers().unwrap().clone();
+        assert_eq!(
+            header.iter().take(5).collect::<Vec<_>>(),
+            ["schema_version", "mode", "format", "seed", "finish_time_ms"]
+        );
+        assert!(header.iter().any(|column| column == "starting_secs"));
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 2);
+        let field = |row: &csv::StringRecord, name: &str| {
+            let column = header.iter().position(|column| column == name).unwrap();
+            row[column].to_string()
+        };
+        assert_eq!(
+            field(&rows[0], "schema_version"),
+            SCHEMA_VERSION.to_string()
+        );
+        assert_eq!(field(&rows[0], "mode"), "addition");
+        assert_eq!(field(&rows[0], "seed"), "7");
+        assert_eq!(field(&rows[0], "correct"), "true");
+        assert_eq!(field(&rows[0], "latency_ms"), "2000");
+        assert_eq!(field(&rows[0], "kind"), "addition");
+        assert_eq!(field(&rows[1], "index"), "1");
+        assert_eq!(field(&rows[1], "given"), "nope");
+        assert_eq!(field(&rows[1], "correct"), "false");
+
+        let path = dir.join("session.json");
+        export.write(&path, ExportFormat::Json).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["format"], "timed");
+        assert_eq!(value["finish_time_ms"], serde_json::Value::Null);
+        let question = value["questions"][0].as_object().unwrap();
+        let mut fields: Vec<_> = question.keys().map(String::as_str).collect();
+        fields.sort_unstable();
+        assert_eq!(
+            fields,
+            [
+                "correct",
+                "expected",
+                "given",
+                "index",
+                "kind",
+                "latency_ms",
+                "question"
+            ]
+        );
+        assert_eq!(
+            serde_json::from_str::<SessionExport>(&text).unwrap(),
+            export
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
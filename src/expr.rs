@@ -0,0 +1,503 @@
+use crate::rational::Rational;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+/// A binary arithmetic operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    /// Raising to a small whole power, written `^`.
+    Pow,
+}
+
+impl Op {
+    /// How tightly the operator binds: 1 for `+ -`, 2 for `* /` and 3 for
+    /// `^`.
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Add | Op::Sub => 1,
+            Op::Mul | Op::Div => 2,
+            Op::Pow => 3,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::Pow => "^",
+        }
+    }
+
+    fn apply(self, lhs: Rational, rhs: Rational) -> Result<Rational, EvalError> {
+        match self {
+            Op::Add => lhs.checked_add(rhs).ok_or(EvalError::Overflow),
+            Op::Sub => lhs.checked_sub(rhs).ok_or(EvalError::Overflow),
+            Op::Mul => lhs.checked_mul(rhs).ok_or(EvalError::Overflow),
+            Op::Div => {
+                if rhs == Rational::ZERO {
+                    return Err(EvalError::DivisionByZero);
+                }
+                lhs.checked_div(rhs).ok_or(EvalError::Overflow)
+            }
+            Op::Pow => {
+                let exponent = rhs
+                    .is_integer()
+                    .then(|| u32::try_from(rhs.numer()).ok())
+                    .flatten()
+                    .filter(|&exponent| exponent <= MAX_EXPONENT)
+                    .ok_or(EvalError::InvalidExponent)?;
+                (0..exponent).try_fold(Rational::integer(1), |acc, _| {
+                    acc.checked_mul(lhs).ok_or(EvalError::Overflow)
+                })
+            }
+        }
+    }
+}
+
+/// Largest exponent `^` accepts, which keeps powers cheap to evaluate.
+const MAX_EXPONENT: u32 = 10;
+
+/// Why an expression could not be evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalError {
+    DivisionByZero,
+    /// The result is a fraction where an integer was expected.
+    InexactDivision,
+    /// An exponent that is negative, fractional or too large.
+    InvalidExponent,
+    /// The square root of a number that is not a perfect square.
+    InexactRoot,
+    /// The expression contains `x`, which has no value.
+    UnknownVariable,
+    Overflow,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+            EvalError::InexactDivision => write!(f, "division with a remainder"),
+            EvalError::InvalidExponent => write!(f, "exponent is not a small whole number"),
+            EvalError::InexactRoot => write!(f, "square root of a non-square"),
+            EvalError::UnknownVariable => write!(f, "unknown value of x"),
+            EvalError::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+/// An arithmetic expression. The same tree renders the question text and
+/// computes the answer, so the two can never disagree: `Display` adds the
+/// parentheses the tree's shape needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Num(i32),
+    /// A fraction literal, kept as written so `6/8` can be shown unreduced.
+    Frac {
+        numer: i32,
+        denom: i32,
+    },
+    /// A decimal literal: `scaled / 10^places`, shown with every place.
+    Dec {
+        scaled: i32,
+        places: u32,
+    },
+    Binary {
+        op: Op,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    /// The unknown `x` in an equation.
+    Var,
+    /// A square root, written `√`, that must come out exact.
+    Sqrt(Box<Expr>),
+    Paren(Box<Expr>),
+}
+
+impl Expr {
+    pub fn binary(op: Op, lhs: Expr, rhs: Expr) -> Self {
+        Expr::Binary {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    pub fn paren(inner: Expr) -> Self {
+        Expr::Paren(Box::new(inner))
+    }
+
+    pub fn sqrt(inner: Expr) -> Self {
+        Expr::Sqrt(Box::new(inner))
+    }
+
+    /// Replaces every `x` with `value`.
+    pub fn substitute(&self, value: i32) -> Expr {
+        match self {
+            Expr::Var => Expr::Num(value),
+            Expr::Binary { op, lhs, rhs } => {
+                Expr::binary(*op, lhs.substitute(value), rhs.substitute(value))
+            }
+            Expr::Sqrt(inner) => Expr::sqrt(inner.substitute(value)),
+            Expr::Paren(inner) => Expr::paren(inner.substitute(value)),
+            leaf => leaf.clone(),
+        }
+    }
+
+    /// How many operators and roots the expression contains.
+    pub fn operation_count(&self) -> u32 {
+        match self {
+            Expr::Num(_) | Expr::Frac { .. } | Expr::Dec { .. } | Expr::Var => 0,
+            Expr::Binary { lhs, rhs, .. } => 1 + lhs.operation_count() + rhs.operation_count(),
+            Expr::Sqrt(inner) => 1 + inner.operation_count(),
+            Expr::Paren(inner) => inner.operation_count(),
+        }
+    }
+
+    /// Evaluates to an integer, failing if the result is a fraction.
+    pub fn eval(&self) -> Result<i32, EvalError> {
+        let value = self.eval_exact()?;
+        if !value.is_integer() {
+            return Err(EvalError::InexactDivision);
+        }
+        value.numer().try_into().map_err(|_| EvalError::Overflow)
+    }
+
+    /// Evaluates with exact fractions.
+    pub fn eval_exact(&self) -> Result<Rational, EvalError> {
+        match self {
+            Expr::Num(n) => Ok(Rational::from(*n)),
+            Expr::Frac { numer, denom } => {
+                Rational::new((*numer).into(), (*denom).into()).ok_or(EvalError::DivisionByZero)
+            }
+            Expr::Dec { scaled, places } => {
+                let scale = 10i64.checked_pow(*places).ok_or(EvalError::Overflow)?;
+                Rational::new((*scaled).into(), scale).ok_or(EvalError::Overflow)
+            }
+            Expr::Binary { op, lhs, rhs } => op.apply(lhs.eval_exact()?, rhs.eval_exact()?),
+            Expr::Sqrt(inner) => inner
+                .eval_exact()?
+                .checked_sqrt()
+                .ok_or(EvalError::InexactRoot),
+            Expr::Var => Err(EvalError::UnknownVariable),
+            Expr::Paren(inner) => inner.eval_exact(),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, true)
+    }
+}
+
+impl Expr {
+    /// How tightly the outermost operation binds, from 1 for `+ -` to 4 for
+    /// literals, roots and parentheses.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary { op, .. } => op.precedence(),
+            _ => 4,
+        }
+    }
+
+    /// Negative numbers are parenthesized unless they come first, so
+    /// `-3 - (-7)` never shows two signs in a row. Operands that bind more
+    /// loosely than their operator are parenthesized too, as is a right
+    /// operand that binds equally, since the operators group to the left.
+    fn write(&self, f: &mut fmt::Formatter<'_>, leading: bool) -> fmt::Result {
+        let negative = matches!(
+            *self,
+            Expr::Num(n) | Expr::Frac { numer: n, .. } | Expr::Dec { scaled: n, .. } if n < 0
+        );
+        if negative && !leading {
+            return write!(f, "({})", self);
+        }
+        match self {
+            Expr::Num(n) => write!(f, "{}", n),
+            Expr::Frac { numer, denom } => write!(f, "{}/{}", numer, denom),
+            Expr::Dec { places, .. } => {
+                let value = self.eval_exact().map_err(|_| fmt::Error)?;
+                write!(f, "{}", value.to_decimal_string(*places))
+            }
+            Expr::Var => write!(f, "x"),
+            // A positive coefficient is written next to the unknown, as `3x`
+            Expr::Binary {
+                op: Op::Mul,
+                lhs,
+                rhs,
+            } if matches!((&**lhs, &**rhs), (Expr::Num(n), Expr::Var) if *n > 0) => {
+                write!(f, "{}x", lhs)
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                // `^` groups to the right instead
+                let (lhs_min, rhs_min) = match op {
+                    Op::Pow => (4, 3),
+                    _ => (op.precedence(), op.precedence() + 1),
+                };
+                // A fraction next to * or / needs parentheses to keep its
+                // meaning when read with the usual precedence
+                let bracket = |expr: &Expr, min: u8| {
+                    expr.precedence() < min
+                        || matches!(expr, Expr::Frac { .. })
+                            && matches!(op, Op::Mul | Op::Div | Op::Pow)
+                };
+                if bracket(lhs, lhs_min) {
+                    write!(f, "({})", lhs)?;
+                } else {
+                    // `-2 ^ 2` would read as -(2 ^ 2)
+                    lhs.write(f, leading && *op != Op::Pow)?;
+                }
+                write!(f, " {} ", op.symbol())?;
+                if bracket(rhs, rhs_min) {
+                    write!(f, "({})", rhs)
+                } else {
+                    rhs.write(f, false)
+                }
+            }
+            Expr::Sqrt(inner) if inner.precedence() < 4 => write!(f, "√({})", inner),
+            Expr::Sqrt(inner) => {
+                write!(f, "√")?;
+                inner.write(f, false)
+            }
+            Expr::Paren(inner) => write!(f, "({})", inner),
+        }
+    }
+}
+
+/// Why a string could not be parsed as an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    /// Parses the notation produced by `Display`, with the usual precedence:
+    /// `√` binds tightest, then the right-associative `^`, then `*` and `/`,
+    /// then `+` and `-`, the last four left-associative.
+    /// A fraction written without spaces, like `3/4`, is a single literal,
+    /// and so is a decimal like `2.50`. A `-` directly before a digit, where
+    /// an operand is expected, makes the literal negative. `x` is the
+    /// unknown, and `3x` multiplies it by a coefficient.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.char_indices().peekable(),
+            len: s.len(),
+        };
+        let expr = parser.sum()?;
+        parser.skip_whitespace();
+        match parser.chars.peek() {
+            None => Ok(expr),
+            Some(&(position, c)) => Err(ParseError {
+                position,
+                message: format!("unexpected '{}'", c),
+            }),
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    len: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|&(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn position(&mut self) -> usize {
+        self.chars.peek().map_or(self.len, |&(i, _)| i)
+    }
+
+    fn error(&mut self, message: &str) -> ParseError {
+        ParseError {
+            position: self.position(),
+            message: message.to_string(),
+        }
+    }
+
+    fn operator(&mut self, ops: &[(char, Op)]) -> Option<Op> {
+        self.skip_whitespace();
+        let &(_, c) = self.chars.peek()?;
+        let &(_, op) = ops.iter().find(|&&(symbol, _)| symbol == c)?;
+        self.chars.next();
+        Some(op)
+    }
+
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.product()?;
+        while let Some(op) = self.operator(&[('+', Op::Add), ('-', Op::Sub)]) {
+            expr = Expr::binary(op, expr, self.product()?);
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.power()?;
+        while let Some(op) = self.operator(&[('*', Op::Mul), ('/', Op::Div)]) {
+            expr = Expr::binary(op, expr, self.power()?);
+        }
+        Ok(expr)
+    }
+
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.atom()?;
+        match self.operator(&[('^', Op::Pow)]) {
+            Some(op) => Ok(Expr::binary(op, base, self.power()?)),
+            None => Ok(base),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        self.skip_whitespace();
+        if self.chars.next_if(|&(_, c)| c == '√').is_some() {
+            return Ok(Expr::sqrt(self.atom()?));
+        }
+        if self.chars.next_if(|&(_, c)| c == '(').is_some() {
+            let inner = self.sum()?;
+            self.skip_whitespace();
+            if self.chars.next_if(|&(_, c)| c == ')').is_none() {
+                return Err(self.error("expected ')'"));
+            }
+            return Ok(Expr::paren(inner));
+        }
+
+        let negative = {
+            let mut lookahead = self.chars.clone();
+            lookahead.next().is_some_and(|(_, c)| c == '-')
+                && lookahead.next().is_some_and(|(_, c)| c.is_ascii_digit())
+        };
+        if negative {
+            self.chars.next();
+        } else if self.chars.next_if(|&(_, c)| c == 'x').is_some() {
+            return Ok(Expr::Var);
+        }
+        let sign = if negative { -1 } else { 1 };
+
+        let numer = sign * self.number()?;
+        if self.chars.next_if(|&(_, c)| c == 'x').is_some() {
+            return Ok(Expr::binary(Op::Mul, Expr::Num(numer), Expr::Var));
+        }
+        if self.chars.next_if(|&(_, c)| c == '.').is_some() {
+            let start = self.position();
+            let fraction = self.number()?;
+            let places = (self.position() - start) as u32;
+            let scaled = 10i32
+                .checked_pow(places)
+                .and_then(|scale| numer.checked_mul(scale)?.checked_add(sign * fraction))
+                .ok_or_else(|| self.error("number too large"))?;
+            return Ok(Expr::Dec { scaled, places });
+        }
+
+        // `3/4` written without spaces is a fraction literal rather than a
+        // division, matching how `Display` renders `Expr::Frac`
+        let mut lookahead = self.chars.clone();
+        let is_fraction = lookahead.next().is_some_and(|(_, c)| c == '/')
+            && lookahead.next().is_some_and(|(_, c)| c.is_ascii_digit());
+        if !is_fraction {
+            return Ok(Expr::Num(numer));
+        }
+        self.chars.next();
+        let denom = self.number()?;
+        Ok(Expr::Frac { numer, denom })
+    }
+
+    fn number(&mut self) -> Result<i32, ParseError> {
+        let mut digits = String::new();
+        while let Some((_, c)) = self.chars.next_if(|&(_, c)| c.is_ascii_digit()) {
+            digits.push(c);
+        }
+        if digits.is_empty() {
+            return Err(self.error("expected a number"));
+        }
+        digits.parse().map_err(|_| self.error("number too large"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(n: i32) -> Expr {
+        Expr::Num(n)
+    }
+
+    #[test]
+    fn display_brackets_by_precedence() {
+        let sum = Expr::binary(Op::Add, num(3), num(4));
+        let product = Expr::binary(Op::Mul, num(2), sum.clone());
+        assert_eq!(product.to_string(), "2 * (3 + 4)");
+        assert_eq!(product.eval(), Ok(14));
+
+        let cases = [
+            (Expr::binary(Op::Add, num(2), sum.clone()), "2 + (3 + 4)"),
+            (Expr::binary(Op::Sub, sum.clone(), num(1)), "3 + 4 - 1"),
+            (
+                Expr::binary(Op::Div, Expr::binary(Op::Mul, num(6), num(4)), num(3)),
+                "6 * 4 / 3",
+            ),
+            (
+                Expr::binary(Op::Pow, Expr::binary(Op::Pow, num(2), num(3)), num(2)),
+                "(2 ^ 3) ^ 2",
+            ),
+            (
+                Expr::binary(Op::Pow, num(2), Expr::binary(Op::Pow, num(3), num(2))),
+                "2 ^ 3 ^ 2",
+            ),
+            (Expr::sqrt(sum.clone()), "√(3 + 4)"),
+            (Expr::binary(Op::Sub, num(-3), num(-7)), "-3 - (-7)"),
+            (Expr::paren(sum), "(3 + 4)"),
+        ];
+        for (expr, text) in cases {
+            assert_eq!(expr.to_string(), text);
+            let parsed: Expr = text.parse().unwrap();
+            assert_eq!(parsed.eval_exact(), expr.eval_exact(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_follows_the_usual_precedence() {
+        let cases = [
+            ("2 + 3 * 4", 14),
+            ("(2 + 3) * 4", 20),
+            ("2 ^ 3 ^ 2", 512),
+            ("√16 + 9", 13),
+            ("20 - 6 - 4", 10),
+            ("3x + 4", 10),
+        ];
+        for (text, value) in cases {
+            let expr: Expr = text.parse().unwrap();
+            assert_eq!(expr.substitute(2).eval(), Ok(value), "{}", text);
+        }
+        for (text, value) in [
+            ("-2.5 + 1", "-3/2"),
+            ("-0.5 * 4", "-2"),
+            ("1 - (-3/4)", "7/4"),
+        ] {
+            let expr: Expr = text.parse().unwrap();
+            assert_eq!(expr.to_string(), text);
+            assert_eq!(
+                expr.eval_exact().map(|v| v.to_string()),
+                Ok(value.to_string())
+            );
+        }
+        assert!("2 + + 3".parse::<Expr>().is_err());
+        assert!("(2 + 3".parse::<Expr>().is_err());
+    }
+}
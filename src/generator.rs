@@ -0,0 +1,921 @@
+use crate::answer::Answer;
+use crate::expr::{Expr, Op};
+use crate::rational::Rational;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// A single quiz question together with its answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub question: String,
+    pub answer: Answer,
+    pub kind: ProblemKind,
+    /// Operators and roots in the question; PEMDAS answers score per
+    /// operation.
+    pub operations: u32,
+    /// Fixed points for a correct answer, overriding the usual scoring.
+    pub points: Option<i32>,
+}
+
+impl Problem {
+    pub fn is_pemdas(&self) -> bool {
+        self.kind == ProblemKind::Pemdas
+    }
+}
+
+/// The skill a problem exercises, used to break down statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    Addition,
+    Subtraction,
+    Multiplication,
+    Division,
+    /// A multi-step order-of-operations problem.
+    Pemdas,
+    /// Arithmetic on fractions, or simplifying one.
+    Fraction,
+    /// Adding, subtracting or multiplying decimals.
+    Decimal,
+    /// A percentage of a number, or a percent change.
+    Percentage,
+    /// Any of the four operations on signed integers.
+    Integer,
+    /// A linear equation to solve for `x`.
+    Algebra,
+    /// Big numbers to answer roughly rather than exactly.
+    Estimation,
+    /// A question from a custom problem set.
+    Custom,
+}
+
+impl ProblemKind {
+    pub const ALL: [ProblemKind; 12] = [
+        ProblemKind::Addition,
+        ProblemKind::Subtraction,
+        ProblemKind::Multiplication,
+        ProblemKind::Division,
+        ProblemKind::Pemdas,
+        ProblemKind::Fraction,
+        ProblemKind::Decimal,
+        ProblemKind::Percentage,
+        ProblemKind::Integer,
+        ProblemKind::Algebra,
+        ProblemKind::Estimation,
+        ProblemKind::Custom,
+    ];
+
+    /// Position in [`ProblemKind::ALL`].
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Identifier used in exported files.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProblemKind::Addition => "addition",
+            ProblemKind::Subtraction => "subtraction",
+            ProblemKind::Multiplication => "multiplication",
+            ProblemKind::Division => "division",
+            ProblemKind::Pemdas => "pemdas",
+            ProblemKind::Fraction => "fraction",
+            ProblemKind::Decimal => "decimal",
+            ProblemKind::Percentage => "percentage",
+            ProblemKind::Integer => "integer",
+            ProblemKind::Algebra => "algebra",
+            ProblemKind::Estimation => "estimation",
+            ProblemKind::Custom => "custom",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ProblemKind::Addition => "Addition",
+            ProblemKind::Subtraction => "Subtraction",
+            ProblemKind::Multiplication => "Multiplication",
+            ProblemKind::Division => "Division",
+            ProblemKind::Pemdas => "PEMDAS",
+            ProblemKind::Fraction => "Fractions",
+            ProblemKind::Decimal => "Decimals",
+            ProblemKind::Percentage => "Percentages",
+            ProblemKind::Integer => "Signed integers",
+            ProblemKind::Algebra => "Algebra",
+            ProblemKind::Estimation => "Estimation",
+            ProblemKind::Custom => "Custom",
+        }
+    }
+
+    fn of(operator: Op) -> Self {
+        match operator {
+            Op::Add => ProblemKind::Addition,
+            Op::Sub => ProblemKind::Subtraction,
+            Op::Mul => ProblemKind::Multiplication,
+            Op::Div => ProblemKind::Division,
+            // Powers only appear inside order-of-operations problems
+            Op::Pow => ProblemKind::Pemdas,
+        }
+    }
+}
+
+/// How hard the generated problems are: the operand range for each kind of
+/// problem, and how often mixed play throws in a PEMDAS question.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difficulty {
+    /// Overall level, from 1 up to [`Difficulty::MAX_LEVEL`].
+    pub level: u32,
+    pub pemdas_chance: f64,
+    max_operand: [i32; ProblemKind::ALL.len()],
+}
+
+impl Difficulty {
+    pub const MAX_LEVEL: u32 = 10;
+
+    /// Numbers 1–10, no PEMDAS
+    pub const EASY: Difficulty = Difficulty::uniform(1, 0.0);
+    /// Numbers 1–20, occasional PEMDAS
+    pub const MEDIUM: Difficulty = Difficulty::uniform(3, 0.3);
+    /// Numbers 1–50, frequent PEMDAS
+    pub const HARD: Difficulty = Difficulty::uniform(9, 0.3);
+
+    /// Every kind of problem at the same level.
+    pub const fn uniform(level: u32, pemdas_chance: f64) -> Self {
+        Self {
+            level,
+            pemdas_chance,
+            max_operand: [Self::max_operand_at(level); ProblemKind::ALL.len()],
+        }
+    }
+
+    /// Every kind of problem at `level`. PEMDAS shows up from level 2 and
+    /// becomes more frequent higher up, capped at half of all mixed
+    /// questions.
+    pub fn at_level(level: u32) -> Self {
+        Self::uniform(level, (0.1 * (level - 1) as f64).min(0.5))
+    }
+
+    /// Largest operand at `level`: 10 at level 1, growing by 5 per level.
+    pub const fn max_operand_at(level: u32) -> i32 {
+        5 + 5 * level as i32
+    }
+
+    /// Overrides the level of a single kind of problem.
+    pub fn with_kind_level(mut self, kind: ProblemKind, level: u32) -> Self {
+        self.max_operand[kind.index()] = Self::max_operand_at(level);
+        self
+    }
+
+    pub fn max_operand(&self, kind: ProblemKind) -> i32 {
+        self.max_operand[kind.index()]
+    }
+
+    /// Times tables and denominators grow towards 12 instead of using the
+    /// general ranges.
+    fn table_max(&self, kind: ProblemKind) -> i32 {
+        (self.max_operand(kind) / 2).clamp(2, 12)
+    }
+}
+
+/// Which kinds of problems to generate, for drilling a single skill.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PracticeMode {
+    /// Every operation plus occasional PEMDAS, the classic game.
+    #[default]
+    Mixed,
+    Addition,
+    /// Multiplication within the 1–12 tables.
+    TimesTables,
+    /// Divisions that undo a times-table fact.
+    DivisionFacts,
+    /// Multi-step order-of-operations problems only.
+    Pemdas,
+    /// Add, subtract, multiply, divide and simplify fractions.
+    Fractions,
+    /// Add, subtract and multiply decimals.
+    Decimals,
+    /// Percentages of numbers and percent changes.
+    Percentages,
+    /// The four operations with negative operands, like `-3 - (-7)`.
+    Integers,
+    /// One- and two-step equations like `3x + 4 = 19`.
+    Algebra,
+    /// Approximate answers to big sums and products, like `487 * 21 ≈ ?`.
+    Estimation,
+    /// Mixed problems interleaved with previously missed facts that are due
+    /// for review.
+    Review,
+    /// Questions from a loaded problem set. Not in [`PracticeMode::ALL`],
+    /// since it needs a set to play.
+    Custom,
+}
+
+impl PracticeMode {
+    pub const ALL: [PracticeMode; 12] = [
+        PracticeMode::Mixed,
+        PracticeMode::Addition,
+        PracticeMode::TimesTables,
+        PracticeMode::DivisionFacts,
+        PracticeMode::Pemdas,
+        PracticeMode::Fractions,
+        PracticeMode::Decimals,
+        PracticeMode::Percentages,
+        PracticeMode::Integers,
+        PracticeMode::Algebra,
+        PracticeMode::Estimation,
+        PracticeMode::Review,
+    ];
+
+    /// Short identifier used on the command line and in saved scores.
+    pub fn name(self) -> &'static str {
+        match self {
+            PracticeMode::Mixed => "mixed",
+            PracticeMode::Addition => "addition",
+            PracticeMode::TimesTables => "times-tables",
+            PracticeMode::DivisionFacts => "division-facts",
+            PracticeMode::Pemdas => "pemdas",
+            PracticeMode::Fractions => "fractions",
+            PracticeMode::Decimals => "decimals",
+            PracticeMode::Percentages => "percentages",
+            PracticeMode::Integers => "integers",
+            PracticeMode::Algebra => "algebra",
+            PracticeMode::Estimation => "estimation",
+            PracticeMode::Review => "review",
+            PracticeMode::Custom => "custom",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PracticeMode::Mixed => "Mixed",
+            PracticeMode::Addition => "Addition only",
+            PracticeMode::TimesTables => "Times tables 1–12",
+            PracticeMode::DivisionFacts => "Division facts",
+            PracticeMode::Pemdas => "PEMDAS only",
+            PracticeMode::Fractions => "Fractions",
+            PracticeMode::Decimals => "Decimals",
+            PracticeMode::Percentages => "Percentages",
+            PracticeMode::Integers => "Integers (negatives)",
+            PracticeMode::Algebra => "Solve for x",
+            PracticeMode::Estimation => "Estimation",
+            PracticeMode::Review => "Review missed facts",
+            PracticeMode::Custom => "Custom problem set",
+        }
+    }
+
+    /// The kinds whose ratings set the mode's overall level. Mixed play
+    /// leaves PEMDAS out, since how often it is asked follows the level.
+    pub fn rated_kinds(self) -> &'static [ProblemKind] {
+        match self {
+            // Custom mode without a set falls back to mixed problems
+            PracticeMode::Mixed | PracticeMode::Review | PracticeMode::Custom => &[
+                ProblemKind::Addition,
+                ProblemKind::Subtraction,
+                ProblemKind::Multiplication,
+                ProblemKind::Division,
+            ],
+            PracticeMode::Addition => &[ProblemKind::Addition],
+            PracticeMode::TimesTables => &[ProblemKind::Multiplication],
+            PracticeMode::DivisionFacts => &[ProblemKind::Division],
+            PracticeMode::Pemdas => &[ProblemKind::Pemdas],
+            PracticeMode::Fractions => &[ProblemKind::Fraction],
+            PracticeMode::Decimals => &[ProblemKind::Decimal],
+            PracticeMode::Percentages => &[ProblemKind::Percentage],
+            PracticeMode::Integers => &[ProblemKind::Integer],
+            PracticeMode::Algebra => &[ProblemKind::Algebra],
+            PracticeMode::Estimation => &[ProblemKind::Estimation],
+        }
+    }
+}
+
+impl FromStr for PracticeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|mode| mode.name()).collect();
+                format!(
+                    "unknown mode '{}', expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Produces a reproducible stream of problems: two generators created with
+/// the same seed yield the same questions in the same order.
+pub struct ProblemGenerator {
+    seed: u64,
+    /// Whether the seed was chosen rather than random.
+    fixed_seed: bool,
+    rng: StdRng,
+    mode: PracticeMode,
+    pemdas_depth: u32,
+    /// Problems of a custom set, played instead of generated ones.
+    custom: Vec<Problem>,
+    /// Indices into `custom` not asked yet this round.
+    custom_deck: Vec<usize>,
+}
+
+impl ProblemGenerator {
+    pub const DEFAULT_PEMDAS_DEPTH: u32 = 2;
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            fixed_seed: true,
+            rng: StdRng::seed_from_u64(seed),
+            mode: PracticeMode::default(),
+            pemdas_depth: Self::DEFAULT_PEMDAS_DEPTH,
+            custom: Vec::new(),
+            custom_deck: Vec::new(),
+        }
+    }
+
+    /// Plays `problems` in a shuffled order instead of generating any, going
+    /// through all of them before repeating one.
+    pub fn with_problems(mut self, problems: Vec<Problem>) -> Self {
+        self.mode = PracticeMode::Custom;
+        self.custom = problems;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: PracticeMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets how deeply PEMDAS problems nest their operations; each level
+    /// adds at least one operation.
+    pub fn with_pemdas_depth(mut self, depth: u32) -> Self {
+        self.pemdas_depth = depth;
+        self
+    }
+
+    /// Creates a generator with a random seed. The seed is still recorded so
+    /// the session can be replayed later.
+    pub fn from_entropy() -> Self {
+        Self {
+            fixed_seed: false,
+            ..Self::new(rand::thread_rng().gen())
+        }
+    }
+
+    /// Whether the generator was created with [`ProblemGenerator::new`], so
+    /// the game is expected to be reproducible.
+    pub fn has_fixed_seed(&self) -> bool {
+        self.fixed_seed
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn mode(&self) -> PracticeMode {
+        self.mode
+    }
+
+    pub fn next_problem(&mut self, difficulty: &Difficulty) -> Problem {
+        if !self.custom.is_empty() {
+            if self.custom_deck.is_empty() {
+                self.custom_deck = (0..self.custom.len()).collect();
+                self.custom_deck.shuffle(&mut self.rng);
+            }
+            let index = self.custom_deck.pop().expect("deck was just refilled");
+            return self.custom[index].clone();
+        }
+        generate_problem(&mut self.rng, difficulty, self.mode, self.pemdas_depth)
+    }
+}
+
+fn generate_problem(
+    rng: &mut impl Rng,
+    difficulty: &Difficulty,
+    mode: PracticeMode,
+    pemdas_depth: u32,
+) -> Problem {
+    match mode {
+        PracticeMode::Fractions => {
+            return fraction_problem(rng, difficulty.table_max(ProblemKind::Fraction));
+        }
+        PracticeMode::Decimals => return decimal_problem(rng, difficulty),
+        PracticeMode::Percentages => {
+            return percentage_problem(rng, difficulty.max_operand(ProblemKind::Percentage));
+        }
+        PracticeMode::Integers => return integer_problem(rng, difficulty),
+        PracticeMode::Algebra => return algebra_problem(rng, difficulty),
+        PracticeMode::Estimation => {
+            return estimation_problem(rng, difficulty.max_operand(ProblemKind::Estimation));
+        }
+        _ => {}
+    }
+
+    let operator = match mode {
+        // Review cards are mixed in by the session
+        // Custom mode without a set falls back to mixed problems
+        PracticeMode::Mixed | PracticeMode::Review | PracticeMode::Custom => {
+            if rng.gen_bool(difficulty.pemdas_chance) {
+                None
+            } else {
+                Some([Op::Add, Op::Sub, Op::Mul, Op::Div][rng.gen_range(0..4)])
+            }
+        }
+        PracticeMode::Addition => Some(Op::Add),
+        PracticeMode::TimesTables => Some(Op::Mul),
+        PracticeMode::DivisionFacts => Some(Op::Div),
+        PracticeMode::Pemdas => None,
+        PracticeMode::Fractions
+        | PracticeMode::Decimals
+        | PracticeMode::Percentages
+        | PracticeMode::Integers
+        | PracticeMode::Algebra
+        | PracticeMode::Estimation => unreachable!("handled above"),
+    };
+    let kind = operator.map_or(ProblemKind::Pemdas, ProblemKind::of);
+    let max = match mode {
+        PracticeMode::TimesTables | PracticeMode::DivisionFacts => difficulty.table_max(kind),
+        // Nested operations grow quickly, so keep the leaves small
+        _ if operator.is_none() => difficulty.table_max(kind),
+        _ => difficulty.max_operand(kind),
+    };
+    let expr = match (mode, operator) {
+        (PracticeMode::TimesTables | PracticeMode::DivisionFacts, Some(operator)) => {
+            table_fact(rng, operator, max)
+        }
+        (_, Some(operator)) => simple_expr(rng, operator, 1, max),
+        (_, None) => pemdas_expr(rng, pemdas_depth, max),
+    };
+
+    let answer = expr
+        .eval()
+        .expect("generated operands are positive and small enough to never fail");
+    Problem {
+        question: expr.to_string(),
+        answer: Answer::Integer(answer),
+        kind,
+        operations: expr.operation_count(),
+        points: None,
+    }
+}
+
+/// Fraction arithmetic or simplification with denominators up to `max_denom`.
+fn fraction_problem(rng: &mut impl Rng, max_denom: i32) -> Problem {
+    let mut fraction = || {
+        let denom = rng.gen_range(2..=max_denom);
+        let numer = rng.gen_range(1..denom);
+        Expr::Frac { numer, denom }
+    };
+    let (lhs, rhs) = (fraction(), fraction());
+
+    if rng.gen_ratio(1, 5) {
+        // Scale a fraction up so there is something to simplify
+        let Expr::Frac { numer, denom } = lhs else {
+            unreachable!()
+        };
+        let value = Rational::new(numer.into(), denom.into()).expect("denominator is at least 2");
+        let factor = rng.gen_range(2..=4);
+        let shown = Expr::Frac {
+            numer: value.numer() as i32 * factor,
+            denom: value.denom() as i32 * factor,
+        };
+        return Problem {
+            question: format!("Simplify {}", shown),
+            answer: Answer::Fraction {
+                value,
+                must_simplify: true,
+            },
+            kind: ProblemKind::Fraction,
+            operations: 1,
+            points: None,
+        };
+    }
+
+    let operator = [Op::Add, Op::Sub, Op::Mul, Op::Div][rng.gen_range(0..4)];
+    let expr = Expr::binary(operator, lhs, rhs);
+    let value = expr
+        .eval_exact()
+        .expect("fractions are non-zero and small enough to never fail");
+    Problem {
+        question: expr.to_string(),
+        answer: Answer::Fraction {
+            value,
+            must_simplify: false,
+        },
+        kind: ProblemKind::Fraction,
+        operations: 1,
+        points: None,
+    }
+}
+
+/// A sum, difference or product too big to work out exactly in time, such
+/// as a three-digit number times a two-digit one. Operands scale with `max`.
+fn estimation_problem(rng: &mut impl Rng, max: i32) -> Problem {
+    let operator = [Op::Add, Op::Sub, Op::Mul][rng.gen_range(0..3)];
+    let (num1, num2) = match operator {
+        Op::Mul => (
+            rng.gen_range(10 * max..=50 * max),
+            rng.gen_range(11..=2 * max),
+        ),
+        _ => {
+            let big = rng.gen_range(100 * max..=500 * max);
+            (big, rng.gen_range(10 * max..big))
+        }
+    };
+    let expr = Expr::binary(operator, Expr::Num(num1), Expr::Num(num2));
+    let exact = expr
+        .eval()
+        .expect("operands are positive and small enough to never fail");
+    Problem {
+        question: format!("{} ≈ ?", expr),
+        answer: Answer::Estimate { exact },
+        kind: ProblemKind::Estimation,
+        operations: 1,
+        points: None,
+    }
+}
+
+/// Decimal addition or subtraction with operands up to the kind's range, or
+/// a product of two smaller decimals. Operands have one or two places.
+fn decimal_problem(rng: &mut impl Rng, difficulty: &Difficulty) -> Problem {
+    let operator = [Op::Add, Op::Sub, Op::Mul][rng.gen_range(0..3)];
+    let max = match operator {
+        Op::Mul => difficulty.table_max(ProblemKind::Decimal),
+        _ => difficulty.max_operand(ProblemKind::Decimal),
+    };
+    let mut decimal = || {
+        let places = rng.gen_range(1..=2);
+        let scale = 10i32.pow(places);
+        // Skip whole numbers so every operand really is a decimal
+        let mut scaled = rng.gen_range(1..max * scale);
+        if scaled % scale == 0 {
+            scaled += 1;
+        }
+        (Expr::Dec { scaled, places }, places)
+    };
+    let ((lhs, lhs_places), (rhs, rhs_places)) = (decimal(), decimal());
+    let places = match operator {
+        Op::Mul => lhs_places + rhs_places,
+        _ => lhs_places.max(rhs_places),
+    };
+
+    let expr = Expr::binary(operator, lhs, rhs);
+    let value = expr
+        .eval_exact()
+        .expect("decimals are small enough to never overflow");
+    Problem {
+        question: expr.to_string(),
+        answer: Answer::Decimal { value, places },
+        kind: ProblemKind::Decimal,
+        operations: 1,
+        points: None,
+    }
+}
+
+/// "15% of 80" or "Percent change from 40 to 50". Bases are multiples of 20
+/// and percentages multiples of 5, so answers come out as short decimals.
+fn percentage_problem(rng: &mut impl Rng, max: i32) -> Problem {
+    let (question, value) = if rng.gen_bool(0.5) {
+        let percent = 5 * rng.gen_range(1..=20);
+        let base = 20 * rng.gen_range(1..=max / 2);
+        let value = Rational::new(i64::from(percent * base), 100).expect("non-zero denominator");
+        (format!("{}% of {}", percent, base), value)
+    } else {
+        // Only these bases divide 100 * a multiple of 5 exactly
+        let base = 20 * [1, 2, 4, 5, 10][rng.gen_range(0..5)];
+        let mut change = 5 * rng.gen_range(1..base / 5);
+        if rng.gen_bool(0.5) {
+            change = -change;
+        }
+        let value =
+            Rational::new(i64::from(change * 100), i64::from(base)).expect("non-zero denominator");
+        (
+            format!("Percent change from {} to {}", base, base + change),
+            value,
+        )
+    };
+    let places = value
+        .decimal_places()
+        .expect("percentages are built to terminate");
+    Problem {
+        question,
+        answer: Answer::Decimal { value, places },
+        kind: ProblemKind::Percentage,
+        operations: 1,
+        points: None,
+    }
+}
+
+/// A one- or two-step linear equation such as `x - 3 = 5` or `3x + 4 = 19`.
+/// The solution is picked first and the right-hand side computed from it,
+/// so it is always a whole number.
+fn algebra_problem(rng: &mut impl Rng, difficulty: &Difficulty) -> Problem {
+    let max = difficulty.max_operand(ProblemKind::Algebra);
+    let coefficient = rng.gen_range(2..=difficulty.table_max(ProblemKind::Algebra));
+    let constant = rng.gen_range(1..=max);
+    let mut solution = rng.gen_range(1..=max);
+
+    let scaled = match rng.gen_range(0..3) {
+        0 => Expr::Var,
+        1 => Expr::binary(Op::Mul, Expr::Num(coefficient), Expr::Var),
+        _ => {
+            // Keep the division exact
+            solution *= coefficient;
+            Expr::binary(Op::Div, Expr::Var, Expr::Num(coefficient))
+        }
+    };
+    let lhs = match (&scaled, rng.gen_range(0..3)) {
+        // `x` alone needs a constant to be an equation at all
+        (Expr::Var, 0) | (_, 1) => Expr::binary(Op::Add, scaled, Expr::Num(constant)),
+        (Expr::Var, _) | (_, 2) => Expr::binary(Op::Sub, scaled, Expr::Num(constant)),
+        _ => scaled,
+    };
+    let rhs = lhs
+        .substitute(solution)
+        .eval()
+        .expect("the solution is chosen so every step is exact");
+
+    Problem {
+        question: format!("{} = {}", lhs, rhs),
+        answer: Answer::Integer(solution),
+        kind: ProblemKind::Algebra,
+        operations: lhs.operation_count(),
+        points: None,
+    }
+}
+
+/// One of the four operations on operands in `-max..=max`, at least one of
+/// them negative. Multiplication and division use the times-table range.
+fn integer_problem(rng: &mut impl Rng, difficulty: &Difficulty) -> Problem {
+    let operator = [Op::Add, Op::Sub, Op::Mul, Op::Div][rng.gen_range(0..4)];
+    let max = match operator {
+        Op::Mul | Op::Div => difficulty.table_max(ProblemKind::Integer),
+        _ => difficulty.max_operand(ProblemKind::Integer),
+    };
+    let mut signed = || {
+        let n = rng.gen_range(1..=max);
+        if rng.gen_bool(0.5) {
+            -n
+        } else {
+            n
+        }
+    };
+    let (mut num1, num2) = (signed(), signed());
+    if num1 > 0 && num2 > 0 {
+        num1 = -num1;
+    }
+    let expr = match operator {
+        Op::Div => Expr::binary(Op::Div, Expr::Num(num1 * num2), Expr::Num(num2)),
+        _ => Expr::binary(operator, Expr::Num(num1), Expr::Num(num2)),
+    };
+
+    let answer = expr
+        .eval()
+        .expect("operands are non-zero and small enough to never fail");
+    Problem {
+        question: expr.to_string(),
+        answer: Answer::Integer(answer),
+        kind: ProblemKind::Integer,
+        operations: 1,
+        points: None,
+    }
+}
+
+/// `a op b` with operands in `min..=max`. Divisions are built from a
+/// product so they are always exact.
+fn simple_expr(rng: &mut impl Rng, operator: Op, min: i32, max: i32) -> Expr {
+    let num1 = rng.gen_range(min..=max);
+    let num2 = rng.gen_range(min..=max);
+    match operator {
+        Op::Div => Expr::binary(Op::Div, Expr::Num(num1 * num2), Expr::Num(num2)),
+        _ => Expr::binary(operator, Expr::Num(num1), Expr::Num(num2)),
+    }
+}
+
+/// Largest factor in the times tables.
+const TABLE_SIZE: i32 = 12;
+
+/// A fact from one of the full 1–12 tables times a factor in `1..=max`,
+/// so every table comes up from the start and only the other factor
+/// grows. A division divides by the table.
+fn table_fact(rng: &mut impl Rng, operator: Op, max: i32) -> Expr {
+    let table = rng.gen_range(1..=TABLE_SIZE);
+    let other = rng.gen_range(1..=max);
+    match operator {
+        Op::Div => Expr::binary(Op::Div, Expr::Num(table * other), Expr::Num(table)),
+        _ if rng.gen_bool(0.5) => Expr::binary(operator, Expr::Num(other), Expr::Num(table)),
+        _ => Expr::binary(operator, Expr::Num(table), Expr::Num(other)),
+    }
+}
+
+/// Largest value a PEMDAS problem may have at any step.
+const PEMDAS_LIMIT: i32 = 1000;
+
+/// A random order-of-operations expression whose operations nest `depth`
+/// levels deep, with leaves in `1..=max` and a whole-number value at every
+/// step.
+fn pemdas_expr(rng: &mut impl Rng, depth: u32, max: i32) -> Expr {
+    loop {
+        let expr = nested_expr(rng, depth, max);
+        if expr.eval().is_ok_and(|value| value.abs() <= PEMDAS_LIMIT) {
+            return expr;
+        }
+    }
+}
+
+fn nested_expr(rng: &mut impl Rng, depth: u32, max: i32) -> Expr {
+    if depth == 0 {
+        return Expr::Num(rng.gen_range(1..=max));
+    }
+    // The left side always nests the full depth; the other side may not
+    let deep = nested_expr(rng, depth - 1, max);
+    let Ok(value) = deep.eval() else {
+        return deep;
+    };
+    let divisors: Vec<i32> = (2..=max).filter(|d| value != 0 && value % d == 0).collect();
+
+    match rng.gen_range(0..6) {
+        3 if !divisors.is_empty() => {
+            let divisor = divisors[rng.gen_range(0..divisors.len())];
+            Expr::binary(Op::Div, deep, Expr::Num(divisor))
+        }
+        4 if value.abs() <= 12 => {
+            let exponent = if value.abs() <= 5 {
+                rng.gen_range(2..=3)
+            } else {
+                2
+            };
+            Expr::binary(Op::Pow, deep, Expr::Num(exponent))
+        }
+        5 => {
+            // Shift the value onto a perfect square so the root is exact
+            let root = rng.gen_range(2..=12);
+            let shift = root * root - value;
+            let radicand = match shift {
+                0 => deep,
+                1.. => Expr::binary(Op::Add, deep, Expr::Num(shift)),
+                _ => Expr::binary(Op::Sub, deep, Expr::Num(-shift)),
+            };
+            Expr::sqrt(radicand)
+        }
+        2 => Expr::binary(Op::Mul, deep, Expr::Num(rng.gen_range(2..=max))),
+        choice => {
+            let op = if choice == 1 { Op::Sub } else { Op::Add };
+            let other_depth = rng.gen_range(0..depth);
+            let other = nested_expr(rng, other_depth, max);
+            if op == Op::Add && rng.gen_bool(0.5) {
+                Expr::binary(op, other, deep)
+            } else {
+                Expr::binary(op, deep, other)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_problems_round_trip_through_the_evaluator() {
+        for seed in 0..500 {
+            let mode = PracticeMode::ALL[seed as usize % PracticeMode::ALL.len()];
+            let depth = 2 + seed as u32 % 3;
+            let mut generator = ProblemGenerator::new(seed)
+                .with_mode(mode)
+                .with_pemdas_depth(depth);
+            for difficulty in [Difficulty::EASY, Difficulty::MEDIUM, Difficulty::HARD] {
+                for _ in 0..20 {
+                    let problem = generator.next_problem(&difficulty);
+                    if problem.kind == ProblemKind::Percentage {
+                        // Written in words rather than as an expression
+                        continue;
+                    }
+                    if problem.kind == ProblemKind::Algebra {
+                        // Substituting the answer must balance the equation
+                        let (lhs, rhs) = problem.question.split_once(" = ").unwrap();
+                        let parsed: Expr = lhs.parse().unwrap();
+                        assert_eq!(parsed.to_string(), lhs);
+                        let solution = problem.answer.value().numer() as i32;
+                        assert_eq!(
+                            parsed.substitute(solution).eval().map(|n| n.to_string()),
+                            Ok(rhs.to_string()),
+                            "seed {}: {:?}",
+                            seed,
+                            problem.question
+                        );
+                        continue;
+                    }
+                    let question = problem.question.trim_start_matches("Simplify ");
+                    let question = question.trim_end_matches(" ≈ ?");
+                    let parsed: Expr = question.parse().unwrap_or_else(|e| {
+                        panic!("seed {}: {:?} did not parse: {}", seed, question, e)
+                    });
+                    assert_eq!(parsed.to_string(), question);
+                    if problem.is_pemdas() {
+                        assert_eq!(parsed.operation_count(), problem.operations);
+                        assert!(problem.operations >= depth, "{:?}", question);
+                    }
+                    assert_eq!(
+                        parsed.eval_exact(),
+                        Ok(problem.answer.value()),
+                        "seed {}: {:?} is not exactly {}",
+                        seed,
+                        problem.question,
+                        problem.answer
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn modes_constrain_kinds_and_operands() {
+        use ProblemKind::*;
+        for mode in PracticeMode::ALL {
+            let kinds: &[ProblemKind] = match mode {
+                PracticeMode::Mixed | PracticeMode::Review => {
+                    &[Addition, Subtraction, Multiplication, Division, Pemdas]
+                }
+                PracticeMode::Addition => &[Addition],
+                PracticeMode::TimesTables => &[Multiplication],
+                PracticeMode::DivisionFacts => &[Division],
+                PracticeMode::Pemdas => &[Pemdas],
+                PracticeMode::Fractions => &[Fraction],
+                PracticeMode::Decimals => &[Decimal],
+                PracticeMode::Percentages => &[Percentage],
+                PracticeMode::Integers => &[Integer],
+                PracticeMode::Algebra => &[Algebra],
+                PracticeMode::Estimation => &[Estimation],
+                PracticeMode::Custom => unreachable!("not in ALL"),
+            };
+            let mut generator = ProblemGenerator::new(11).with_mode(mode);
+            let mut largest_table = 0;
+            for difficulty in [Difficulty::EASY, Difficulty::MEDIUM, Difficulty::HARD] {
+                for _ in 0..200 {
+                    let problem = generator.next_problem(&difficulty);
+                    assert!(
+                        kinds.contains(&problem.kind),
+                        "{:?} asked {:?}",
+                        mode,
+                        problem
+                    );
+                    let operands = match problem.question.parse() {
+                        Ok(Expr::Binary { lhs, rhs, .. }) => match (*lhs, *rhs) {
+                            (Expr::Num(a), Expr::Num(b)) => Some((a, b)),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    let answer = problem.answer.value().numer();
+                    match (mode, operands) {
+                        (PracticeMode::Addition, Some((a, b))) => {
+                            let max = difficulty.max_operand(Addition);
+                            assert!(a <= max && b <= max, "{}", problem.question);
+                        }
+                        (PracticeMode::TimesTables, Some((a, b))) => {
+                            assert!(a <= 12 && b <= 12, "{}", problem.question);
+                            if difficulty == Difficulty::EASY {
+                                largest_table = largest_table.max(a.max(b));
+                            }
+                        }
+                        (PracticeMode::DivisionFacts, Some((_, b))) => {
+                            assert!(b <= 12 && answer <= 12, "{}", problem.question);
+                            if difficulty == Difficulty::EASY {
+                                largest_table = largest_table.max(b);
+                            }
+                        }
+                        (PracticeMode::Addition, None)
+                        | (PracticeMode::TimesTables, None)
+                        | (PracticeMode::DivisionFacts, None) => {
+                            panic!("{:?} asked {:?}", mode, problem.question)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            if matches!(
+                mode,
+                PracticeMode::TimesTables | PracticeMode::DivisionFacts
+            ) {
+                // Every table comes up even at the easiest level
+                assert_eq!(largest_table, 12, "{:?}", mode);
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_produces_same_sequence() {
+        let mut a = ProblemGenerator::new(42);
+        let mut b = ProblemGenerator::new(42);
+        for difficulty in [Difficulty::EASY, Difficulty::MEDIUM, Difficulty::HARD] {
+            for _ in 0..10 {
+                assert_eq!(a.next_problem(&difficulty), b.next_problem(&difficulty));
+            }
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use crate::session::{GameFormat, GameSession};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How many entries the leaderboard keeps.
+pub const MAX_ENTRIES: usize = 100;
+
+/// How many entries the front ends show after a game.
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// One finished game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub score: i32,
+    pub correct_answers: i32,
+    pub wrong_answers: i32,
+    pub date: DateTime<Utc>,
+    pub mode: String,
+    pub seed: u64,
+    /// Game format name; scores from before formats existed are timed.
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// Finishing time of a sprint, penalties included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_ms: Option<u128>,
+    /// Correct answers the sprint asked for. Sprints of different lengths
+    /// are ranked apart; ones saved before this was stored have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub questions: Option<u32>,
+}
+
+fn default_format() -> String {
+    GameFormat::Timed.name().to_string()
+}
+
+impl ScoreEntry {
+    pub fn from_session(session: &GameSession) -> Self {
+        Self {
+            score: session.score(),
+            correct_answers: session.correct_answers(),
+            wrong_answers: session.wrong_answers(),
+            date: Utc::now(),
+            mode: session.mode().name().to_string(),
+            seed: session.seed(),
+            format: session.format().name().to_string(),
+            time_ms: (session.format() == GameFormat::Sprint)
+                .then(|| session.finish_time().as_millis()),
+            questions: (session.format() == GameFormat::Sprint)
+                .then_some(session.config().sprint_questions),
+        }
+    }
+
+    /// Sort key where lower is better: the fastest time for sprints, the
+    /// highest score otherwise.
+    fn standing(&self) -> i128 {
+        match self.time_ms {
+            Some(time_ms) => time_ms as i128,
+            None => -i128::from(self.score),
+        }
+    }
+}
+
+/// The outcome of [`HighScores::record`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    /// Zero-based position on the entry's board, if it made it on.
+    pub rank: Option<usize>,
+    /// The entry beats every previous score on its board.
+    pub personal_best: bool,
+    /// The board the entry was ranked on: its sprint length, if any.
+    pub questions: Option<u32>,
+}
+
+/// A leaderboard stored as JSON, best result first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    entries: Vec<ScoreEntry>,
+}
+
+impl HighScores {
+    /// `<platform data dir>/rapid_math/highscores.json` for timed games and
+    /// `highscores-<format>.json` beside it for the others, so each format
+    /// has its own leaderboard.
+    pub fn default_path(format: GameFormat) -> Option<PathBuf> {
+        let name = match format {
+            GameFormat::Timed => "highscores.json".to_string(),
+            format => format!("highscores-{}.json", format.name()),
+        };
+        dirs::data_dir().map(|dir| dir.join("rapid_math").join(name))
+    }
+
+    /// Loads the leaderboard, treating a missing file as an empty one.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err),
+        };
+        let mut scores: Self = serde_json::from_str(&json)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        scores.sort();
+        Ok(scores)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, json)
+    }
+
+    /// Loads the leaderboard at `path`, records `entry` and writes it back.
+    pub fn record_at(path: &Path, entry: ScoreEntry) -> io::Result<(Self, Placement)> {
+        let mut scores = Self::load(path)?;
+        let placement = scores.record(entry);
+        scores.save(path)?;
+        Ok((scores, placement))
+    }
+
+    /// Adds `entry`, ranking it only against entries with the same sprint
+    /// length. Each length keeps at most [`MAX_ENTRIES`].
+    pub fn record(&mut self, entry: ScoreEntry) -> Placement {
+        let standing = entry.standing();
+        let questions = entry.questions;
+        let board = |e: &&ScoreEntry| e.questions == questions;
+        let personal_best = self
+            .entries
+            .iter()
+            .filter(board)
+            .all(|e| standing < e.standing());
+        // Ties go below earlier entries
+        let index = self.entries.partition_point(|e| e.standing() <= standing);
+        let rank = self.entries[..index].iter().filter(board).count();
+        self.entries.insert(index, entry);
+        if let Some(last) = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.questions == questions)
+            .nth(MAX_ENTRIES)
+            .map(|(index, _)| index)
+        {
+            self.entries.remove(last);
+        }
+        Placement {
+            rank: (rank < MAX_ENTRIES).then_some(rank),
+            personal_best,
+            questions,
+        }
+    }
+
+    /// The best `n` entries among sprints of `questions` correct answers,
+    /// or among all entries of other formats when it is `None`.
+    pub fn top(&self, questions: Option<u32>, n: usize) -> Vec<&ScoreEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.questions == questions)
+            .take(n)
+            .collect()
+    }
+
+    fn sort(&mut self) {
+        self.entries.sort_by_key(ScoreEntry::standing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: i32) -> ScoreEntry {
+        ScoreEntry {
+            score,
+            correct_answers: score,
+            wrong_answers: 0,
+            date: Utc::now(),
+            mode: "mixed".to_string(),
+            seed: 0,
+            format: default_format(),
+            time_ms: None,
+            questions: None,
+        }
+    }
+
+    #[test]
+    fn record_keeps_entries_sorted_and_flags_personal_bests() {
+        let mut scores = HighScores::default();
+        assert!(scores.record(entry(5)).personal_best);
+        let placement = scores.record(entry(3));
+        assert_eq!(placement.rank, Some(1));
+        assert!(!placement.personal_best);
+        let placement = scores.record(entry(5));
+        assert_eq!(placement.rank, Some(1));
+        assert!(!placement.personal_best);
+        assert!(scores.record(entry(9)).personal_best);
+
+        let top: Vec<i32> = scores.top(None, 10).iter().map(|e| e.score).collect();
+        assert_eq!(top, [9, 5, 5, 3]);
+    }
+
+    #[test]
+    fn sprint_times_rank_fastest_first() {
+        let sprint = |time_ms| ScoreEntry {
+            format: GameFormat::Sprint.name().to_string(),
+            time_ms: Some(time_ms),
+            questions: Some(20),
+            ..entry(20)
+        };
+        let mut scores = HighScores::default();
+        scores.record(sprint(45_000));
+        assert!(scores.record(sprint(30_000)).personal_best);
+        assert_eq!(scores.record(sprint(60_000)).rank, Some(2));
+
+        let json = serde_json::to_string(&scores).unwrap();
+        let mut loaded: HighScores = serde_json::from_str(&json).unwrap();
+        loaded.sort();
+        let times: Vec<_> = loaded.top(Some(20), 10).iter().map(|e| e.time_ms).collect();
+        assert_eq!(times, [Some(30_000), Some(45_000), Some(60_000)]);
+
+        // A much shorter sprint gets a board of its own
+        let short = ScoreEntry {
+            questions: Some(1),
+            ..sprint(2_000)
+        };
+        let placement = loaded.record(short);
+        assert_eq!((placement.rank, placement.questions), (Some(0), Some(1)));
+        assert_eq!(loaded.record(sprint(40_000)).rank, Some(1));
+        assert!(!loaded.record(sprint(35_000)).personal_best);
+        assert_eq!(loaded.top(Some(20), 10)[0].time_ms, Some(30_000));
+        assert_eq!(loaded.top(Some(1), 10).len(), 1);
+    }
+}
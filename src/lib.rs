@@ -0,0 +1,13 @@
+pub mod adaptive;
+pub mod answer;
+pub mod choices;
+pub mod config;
+pub mod export;
+pub mod expr;
+pub mod generator;
+pub mod highscores;
+pub mod problem_set;
+pub mod rational;
+pub mod review;
+pub mod session;
+pub mod stats;
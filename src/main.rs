@@ -1,56 +1,149 @@
+mod editor;
+mod tui;
+
+use chrono::Utc;
+use editor::{EditorAction, ProblemSetEditor};
 use eframe::egui;
-use rand::Rng;
-use std::time::{Duration, Instant};
+use rapid_math::answer::{EstimationBand, FractionStrictness};
+use rapid_math::choices::CHOICE_COUNT;
+use rapid_math::config::{GameConfig, StreakTier};
+use rapid_math::export::{ExportFormat, SessionExport};
+use rapid_math::generator::{PracticeMode, ProblemGenerator};
+use rapid_math::highscores::{HighScores, Placement, ScoreEntry, LEADERBOARD_SIZE};
+use rapid_math::problem_set::ProblemSet;
+use rapid_math::review::ReviewDeck;
+use rapid_math::session::{GameFormat, GameSession, SubmitOutcome};
+use rapid_math::stats::SessionStats;
+use std::path::PathBuf;
+use std::time::Instant;
 
 struct MathQuizApp {
-    question: String,
-    answer: i32,
+    session: GameSession,
     user_input: String,
-    score: i32,
-    correct_answers: i32,
-    wrong_answers: i32,
-    remaining_time: Duration,
-    start_time: Option<Instant>,
     feedback: String,
-    game_over: bool,
-    is_pemdas: bool,
+    config: GameConfig,
+    /// Where the settings screen saves to.
+    config_path: Option<PathBuf>,
+    /// Seed given on the command line, taking precedence over the config.
+    seed_override: Option<u64>,
+    mode: PracticeMode,
+    format: GameFormat,
+    /// Set loaded with `--problems` or written in the editor, played in
+    /// custom mode.
+    problem_set: Option<ProblemSet>,
+    /// File the set came from, offered again when editing it.
+    problem_set_path: Option<PathBuf>,
+    /// File every finished game is exported to, from `--export`.
+    export_path: Option<PathBuf>,
+    /// Result of the last export, shown on the game-over screen.
+    export_status: Option<String>,
+    /// Set when the finished game could not be added to the review deck.
+    review_error: Option<String>,
+    /// Filled in once the finished game has been recorded.
+    leaderboard: Option<Result<(HighScores, Placement), String>>,
+    /// The settings being edited, while the settings screen is open.
+    settings: Option<SettingsDraft>,
+    /// Open while a problem set is being written.
+    editor: Option<ProblemSetEditor>,
+}
+
+struct SettingsDraft {
+    config: GameConfig,
+    /// The fixed seed as typed. Kept as text because a `DragValue` goes
+    /// through `f64` and would round seeds above 2^53.
+    seed: Option<String>,
+    error: Option<String>,
 }
 
-impl Default for MathQuizApp {
-    fn default() -> Self {
-        let (question, answer, is_pemdas) = generate_problem(0);
+impl MathQuizApp {
+    /// Takes the seed, format and file paths from `args`; `mode` may differ
+    /// from the one given there when a problem set was loaded.
+    fn new(
+        config: GameConfig,
+        config_path: Option<PathBuf>,
+        mode: PracticeMode,
+        problem_set: Option<ProblemSet>,
+        args: Args,
+    ) -> Self {
+        let session = new_session(&config, args.seed, mode, args.format, problem_set.as_ref());
         Self {
-            question,
-            answer,
+            session,
             user_input: String::new(),
-            score: 0,
-            correct_answers: 0,
-            wrong_answers: 0,
-            remaining_time: Duration::new(30, 0),
-            start_time: None,
             feedback: String::from("Press Start to begin!"),
-            game_over: false,
-            is_pemdas,
+            config,
+            config_path,
+            seed_override: args.seed,
+            mode,
+            format: args.format,
+            problem_set,
+            problem_set_path: args.problems,
+            export_path: args.export,
+            export_status: None,
+            review_error: None,
+            leaderboard: None,
+            settings: None,
+            editor: None,
+        }
+    }
+
+    /// Resets the game state, keeping the settings.
+    fn restart(&mut self) {
+        self.session = new_session(
+            &self.config,
+            self.seed_override,
+            self.mode,
+            self.format,
+            self.problem_set.as_ref(),
+        );
+        self.user_input.clear();
+        self.feedback = String::from("Press Start to begin!");
+        self.leaderboard = None;
+        self.export_status = None;
+        self.review_error = None;
+    }
+
+    fn record_score(&mut self) {
+        let entry = ScoreEntry::from_session(&self.session);
+        let result = match HighScores::default_path(self.format) {
+            Some(path) => HighScores::record_at(&path, entry)
+                .map_err(|err| format!("Could not save high scores: {}", err)),
+            None => Err("No data directory to save high scores in".to_string()),
+        };
+        self.leaderboard = Some(result);
+        self.review_error = save_review(&self.session).err();
+
+        if let Some(path) = self.export_path.clone() {
+            self.export(path);
         }
     }
+
+    fn export(&mut self, path: PathBuf) {
+        let format = ExportFormat::from_path(&path).unwrap_or(ExportFormat::Csv);
+        let export = SessionExport::from_session(&self.session);
+        self.export_status = Some(match export.write(&path, format) {
+            Ok(()) => format!("Exported to {}", path.display()),
+            Err(err) => format!("Could not export to {}: {}", path.display(), err),
+        });
+    }
 }
 
 impl eframe::App for MathQuizApp {
     fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
-        if let Some(start) = self.start_time {
-            let elapsed = start.elapsed();
-            if elapsed >= self.remaining_time {
-                self.remaining_time = Duration::new(0, 0); // Timer hits 0
-                self.game_over = true;
-                self.start_time = None; // Stop the timer
-            } else {
-                self.remaining_time -= elapsed;
-                self.start_time = Some(Instant::now()); // Reset the start time
-            }
+        if let Some(outcome) = self.session.tick(Instant::now()) {
+            // Whatever was typed was meant for the question that ran out
+            self.user_input.clear();
+            self.show_outcome(Some(outcome));
+        }
+        if self.session.is_over() && self.leaderboard.is_none() {
+            self.record_score();
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            if self.game_over {
+            if self.settings.is_some() {
+                self.display_settings(ui);
+            } else if self.editor.is_some() {
+                self.display_editor(ui);
+            } else if self.session.is_over() {
                 self.display_game_over(ui);
             } else {
                 self.display_game(ui, ctx);
@@ -64,163 +157,755 @@ impl eframe::App for MathQuizApp {
 impl MathQuizApp {
     fn display_game(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
         ui.vertical_centered(|ui| {
-            ui.heading("Math Quiz");
+            ui.heading(format!("Math Quiz: {}", self.mode.label()));
             ui.add_space(20.0);
 
             // Timer and Score
-            ui.label(format!("Time Remaining: {} seconds", self.remaining_time.as_secs()));
-            ui.label(format!("Score: {}", self.score));
+            if let Some(left) = self.session.remaining_questions() {
+                ui.label(format!(
+                    "Time: {:.1} seconds",
+                    self.session.finish_time().as_secs_f64()
+                ));
+                ui.label(format!("Questions Left: {}", left));
+            } else {
+                match self.session.lives() {
+                    Some(lives) => ui.label(format!("Lives: {}", "♥".repeat(lives as usize))),
+                    None => ui.label(format!(
+                        "Time Remaining: {} seconds",
+                        self.session.remaining_time().as_secs()
+                    )),
+                };
+                ui.label(format!(
+                    "Score: {}   Streak: {} (x{})",
+                    self.session.score(),
+                    self.session.streak(),
+                    self.session.multiplier()
+                ));
+            }
+            ui.label(format!("Level: {}", self.session.difficulty().level));
 
             // Question and Input
             ui.add_space(30.0);
-            ui.heading(&self.question);
+            ui.heading(&self.session.problem().question);
             ui.add_space(10.0);
-
-            let input_response = ui.add(
-                egui::TextEdit::singleline(&mut self.user_input)
-                    .hint_text("Enter your answer")
-                    .font(egui::FontId::proportional(40.0))
-                    .frame(true),
-            );
-
-            // Automatically focus on the input box
-            if self.start_time.is_some() && !input_response.has_focus() {
-                ui.memory_mut(|mem| mem.request_focus(input_response.id));
+            if let Some(left) = self.session.question_time_left() {
+                let fraction = left.as_secs_f32() / self.session.question_time().as_secs_f32();
+                ui.add(
+                    egui::ProgressBar::new(fraction)
+                        .desired_width(200.0)
+                        .text(format!("{:.1} s", left.as_secs_f32())),
+                );
+                ui.add_space(10.0);
             }
 
-            // Detect Enter Key Submission
-            if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
-                self.process_input();
+            if self.session.choices().is_empty() {
+                self.display_answer_box(ui, ctx);
+            } else {
+                self.display_choices(ui, ctx);
             }
 
             ui.add_space(20.0);
             ui.label(&self.feedback);
 
             // Start Button
-            if self.start_time.is_none() && !self.game_over {
-                if ui.button("Start").clicked() {
-                    self.start_time = Some(Instant::now());
-                    self.feedback = "Solve the problems!".to_string();
+            if !self.session.is_running() && ui.button("Start").clicked() {
+                self.session.start(Instant::now());
+                self.feedback = "Solve the problems!".to_string();
+            }
+            if !self.session.is_running() {
+                self.display_mode_selector(ui);
+            }
+            if !self.session.is_running() && ui.button("Settings").clicked() {
+                self.settings = Some(SettingsDraft {
+                    config: self.config.clone(),
+                    seed: self.config.seed.map(|seed| seed.to_string()),
+                    error: None,
+                });
+            }
+            if !self.session.is_running() && ui.button("Problem Sets").clicked() {
+                self.editor = Some(ProblemSetEditor::new(
+                    self.problem_set.as_ref(),
+                    self.problem_set_path.as_deref(),
+                ));
+            }
+        });
+    }
+
+    fn display_answer_box(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let input_response = ui.add(
+            egui::TextEdit::singleline(&mut self.user_input)
+                .hint_text("Enter your answer")
+                .font(egui::FontId::proportional(40.0))
+                .frame(true),
+        );
+
+        // Automatically focus on the input box
+        if self.session.is_running() && !input_response.has_focus() {
+            ui.memory_mut(|mem| mem.request_focus(input_response.id));
+        }
+
+        // Detect Enter Key Submission
+        if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
+            self.process_input();
+        }
+    }
+
+    /// One big button per option, also reachable with the keys 1–4.
+    fn display_choices(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        const KEYS: [egui::Key; CHOICE_COUNT] = [
+            egui::Key::Num1,
+            egui::Key::Num2,
+            egui::Key::Num3,
+            egui::Key::Num4,
+        ];
+
+        let mut picked = KEYS
+            .iter()
+            .position(|&key| ctx.input(|i| i.key_pressed(key)));
+        ui.horizontal(|ui| {
+            for (index, choice) in self.session.choices().iter().enumerate() {
+                let text = egui::RichText::new(format!("{}) {}", index + 1, choice)).size(32.0);
+                if ui
+                    .add_enabled(self.session.is_running(), egui::Button::new(text))
+                    .clicked()
+                {
+                    picked = Some(index);
                 }
             }
         });
+
+        if let Some(index) = picked {
+            let outcome = self.session.submit_choice(index);
+            self.show_outcome(outcome);
+        }
+    }
+
+    fn display_mode_selector(&mut self, ui: &mut egui::Ui) {
+        let mut mode = self.mode;
+        egui::ComboBox::from_label("Mode")
+            .selected_text(mode.label())
+            .show_ui(ui, |ui| {
+                for option in PracticeMode::ALL {
+                    ui.selectable_value(&mut mode, option, option.label());
+                }
+                if self.problem_set.is_some() {
+                    let custom = PracticeMode::Custom;
+                    ui.selectable_value(&mut mode, custom, custom.label());
+                }
+            });
+        if mode != self.mode {
+            self.mode = mode;
+            self.restart();
+        }
+
+        let mut format = self.format;
+        egui::ComboBox::from_label("Format")
+            .selected_text(format.label())
+            .show_ui(ui, |ui| {
+                for option in GameFormat::ALL {
+                    ui.selectable_value(&mut format, option, option.label());
+                }
+            });
+        if format != self.format {
+            self.format = format;
+            self.restart();
+        }
+    }
+
+    fn display_settings(&mut self, ui: &mut egui::Ui) {
+        let Some(draft) = &mut self.settings else {
+            return;
+        };
+        let mut close = false;
+
+        ui.vertical_centered(|ui| {
+            ui.heading("Settings");
+            ui.add_space(20.0);
+
+            let config = &mut draft.config;
+            egui::Grid::new("settings").num_columns(2).show(ui, |ui| {
+                ui.label("Starting time (s)");
+                ui.add(
+                    egui::DragValue::new(&mut config.starting_secs)
+                        .clamp_range(1..=GameConfig::MAX_SECS as u64),
+                );
+                ui.end_row();
+
+                ui.label("Correct answer bonus (s)");
+                ui.add(
+                    egui::DragValue::new(&mut config.correct_bonus_secs)
+                        .clamp_range(0..=GameConfig::MAX_SECS as u64),
+                );
+                ui.end_row();
+
+                ui.label("Wrong answer penalty (s)");
+                ui.add(
+                    egui::DragValue::new(&mut config.wrong_penalty_secs)
+                        .clamp_range(0..=GameConfig::MAX_SECS as u64),
+                );
+                ui.end_row();
+
+                ui.label("Sprint questions");
+                ui.add(egui::DragValue::new(&mut config.sprint_questions).clamp_range(1..=200));
+                ui.end_row();
+
+                ui.label("Survival lives");
+                ui.add(egui::DragValue::new(&mut config.survival_lives).clamp_range(1..=10));
+                ui.end_row();
+
+                ui.label("Survival questions per level");
+                ui.add(egui::DragValue::new(&mut config.survival_level_every).clamp_range(1..=50));
+                ui.end_row();
+
+                ui.label("Time each question");
+                ui.checkbox(&mut config.question_timer, "");
+                ui.end_row();
+
+                ui.label("Seconds per question");
+                ui.add(
+                    egui::DragValue::new(&mut config.question_secs)
+                        .speed(0.1)
+                        .clamp_range(0.5..=60.0),
+                );
+                ui.end_row();
+
+                ui.label("Seconds off per streak answer");
+                ui.add(
+                    egui::DragValue::new(&mut config.question_streak_shrink_secs)
+                        .speed(0.05)
+                        .clamp_range(0.0..=5.0),
+                );
+                ui.end_row();
+
+                ui.label("Minimum seconds per question");
+                ui.add(
+                    egui::DragValue::new(&mut config.question_min_secs)
+                        .speed(0.1)
+                        .clamp_range(0.5..=60.0),
+                );
+                ui.end_row();
+
+                ui.label("PEMDAS points per operation");
+                ui.add(egui::DragValue::new(&mut config.pemdas_points));
+                ui.end_row();
+
+                ui.label("PEMDAS depth");
+                ui.add(
+                    egui::DragValue::new(&mut config.pemdas_depth)
+                        .clamp_range(2..=GameConfig::MAX_PEMDAS_DEPTH),
+                );
+                ui.end_row();
+
+                ui.label("Fraction answers");
+                egui::ComboBox::from_id_source("fraction_strictness")
+                    .selected_text(config.fraction_strictness.label())
+                    .show_ui(ui, |ui| {
+                        for option in FractionStrictness::ALL {
+                            ui.selectable_value(
+                                &mut config.fraction_strictness,
+                                option,
+                                option.label(),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Decimal tolerance");
+                ui.add(
+                    egui::DragValue::new(&mut config.decimal_tolerance)
+                        .speed(0.01)
+                        .clamp_range(0.0..=10.0),
+                );
+                ui.end_row();
+
+                ui.label("Require every decimal place");
+                ui.checkbox(&mut config.require_decimal_places, "");
+                ui.end_row();
+
+                ui.label("Estimation bands");
+                ui.vertical(|ui| {
+                    let mut remove = None;
+                    for (index, band) in config.estimation_bands.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label("within");
+                            ui.add(
+                                egui::DragValue::new(&mut band.within_percent)
+                                    .speed(0.5)
+                                    .suffix("%"),
+                            );
+                            ui.label("scores");
+                            ui.add(egui::DragValue::new(&mut band.points));
+                            if ui.small_button("Remove").clicked() {
+                                remove = Some(index);
+                            }
+                        });
+                    }
+                    if let Some(index) = remove {
+                        config.estimation_bands.remove(index);
+                    }
+                    if ui.small_button("Add band").clicked() {
+                        let widest = config.estimation_bands.last();
+                        config.estimation_bands.push(EstimationBand {
+                            within_percent: widest.map_or(5.0, |band| band.within_percent * 2.0),
+                            points: 1,
+                        });
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Streak multipliers");
+                ui.vertical(|ui| {
+                    let mut remove = None;
+                    for (index, tier) in config.streak_tiers.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label("from");
+                            ui.add(egui::DragValue::new(&mut tier.streak).clamp_range(1..=100));
+                            ui.label("in a row, x");
+                            ui.add(egui::DragValue::new(&mut tier.multiplier).clamp_range(1..=10));
+                            if ui.small_button("Remove").clicked() {
+                                remove = Some(index);
+                            }
+                        });
+                    }
+                    if let Some(index) = remove {
+                        config.streak_tiers.remove(index);
+                    }
+                    if ui.small_button("Add tier").clicked() {
+                        let longest = config.streak_tiers.last();
+                        config.streak_tiers.push(StreakTier {
+                            streak: longest.map_or(5, |tier| tier.streak + 5),
+                            multiplier: longest.map_or(2, |tier| tier.multiplier + 1),
+                        });
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Speed bonus within (s)");
+                ui.add(
+                    egui::DragValue::new(&mut config.speed_bonus_secs)
+                        .speed(0.1)
+                        .clamp_range(0.0..=30.0),
+                );
+                ui.end_row();
+
+                ui.label("Speed bonus points");
+                ui.add(egui::DragValue::new(&mut config.speed_bonus_points).clamp_range(0..=10));
+                ui.end_row();
+
+                ui.label("Multiple choice");
+                ui.checkbox(&mut config.multiple_choice, "");
+                ui.end_row();
+
+                ui.label("Adaptive difficulty");
+                ui.checkbox(&mut config.adaptive, "");
+                ui.end_row();
+
+                ui.label("Medium from score");
+                ui.add_enabled(
+                    !config.adaptive,
+                    egui::DragValue::new(&mut config.medium_score),
+                );
+                ui.end_row();
+
+                ui.label("Hard from score");
+                ui.add_enabled(
+                    !config.adaptive,
+                    egui::DragValue::new(&mut config.hard_score),
+                );
+                ui.end_row();
+
+                let mut fixed_seed = draft.seed.is_some();
+                ui.checkbox(&mut fixed_seed, "Fixed seed");
+                if fixed_seed {
+                    let seed = draft.seed.get_or_insert_with(|| "0".to_string());
+                    ui.add(egui::TextEdit::singleline(seed).desired_width(180.0));
+                } else {
+                    draft.seed = None;
+                }
+                ui.end_row();
+            });
+
+            // Validate as the user edits so mistakes show up immediately
+            let seed =
+                match &draft.seed {
+                    Some(text) => text.trim().parse().map(Some).map_err(|_| {
+                        format!("the seed must be a whole number from 0 to {}", u64::MAX)
+                    }),
+                    None => Ok(None),
+                };
+            draft.error = match seed {
+                Ok(seed) => {
+                    draft.config.seed = seed;
+                    draft.config.validate().err().map(|err| err.to_string())
+                }
+                Err(err) => Some(err),
+            };
+
+            ui.add_space(20.0);
+            if let Some(error) = &draft.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            ui.horizontal(|ui| {
+                let save = ui.add_enabled(draft.error.is_none(), egui::Button::new("Save"));
+                if save.clicked() {
+                    let saved = match &self.config_path {
+                        Some(path) => draft.config.save(path),
+                        None => Ok(()),
+                    };
+                    match saved {
+                        Ok(()) => {
+                            self.config = draft.config.clone();
+                            close = true;
+                        }
+                        Err(err) => draft.error = Some(err.to_string()),
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    close = true;
+                }
+            });
+        });
+
+        if close {
+            self.settings = None;
+            self.restart();
+        }
+    }
+
+    fn display_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(editor) = &mut self.editor else {
+            return;
+        };
+        match editor.show(ui) {
+            EditorAction::None => {}
+            EditorAction::Play(set) => {
+                self.problem_set = Some(set);
+                self.mode = PracticeMode::Custom;
+                self.editor = None;
+                self.restart();
+            }
+            EditorAction::Close => self.editor = None,
+        }
     }
 
     fn display_game_over(&mut self, ui: &mut egui::Ui) {
         ui.vertical_centered(|ui| {
             ui.heading("Game Over");
             ui.add_space(20.0);
-            ui.label(format!("Final Score: {}", self.score));
-            ui.label(format!("Correct Answers: {}", self.correct_answers));
-            ui.label(format!("Wrong Answers: {}", self.wrong_answers));
+            if self.format == GameFormat::Sprint {
+                ui.label(format!(
+                    "Finish Time: {:.1} seconds",
+                    self.session.finish_time().as_secs_f64()
+                ));
+            } else {
+                ui.label(format!("Final Score: {}", self.session.score()));
+            }
+            ui.label(format!(
+                "Correct Answers: {}",
+                self.session.correct_answers()
+            ));
+            ui.label(format!("Wrong Answers: {}", self.session.wrong_answers()));
+            ui.label(format!("Seed: {}", self.session.seed()));
+
+            if let Some(error) = &self.review_error {
+                ui.label(error);
+            }
+
+            ui.add_space(20.0);
+            self.display_stats(ui);
+
+            ui.add_space(20.0);
+            self.display_leaderboard(ui);
+
+            ui.add_space(20.0);
+            ui.horizontal(|ui| {
+                for format in [ExportFormat::Csv, ExportFormat::Json] {
+                    let label = format!("Export {}", format.extension().to_uppercase());
+                    if ui.button(label).clicked() {
+                        let export = SessionExport::from_session(&self.session);
+                        match export.default_path(format) {
+                            Some(path) => self.export(path),
+                            None => {
+                                self.export_status =
+                                    Some("No data directory to export to".to_string())
+                            }
+                        }
+                    }
+                }
+            });
+            if let Some(status) = &self.export_status {
+                ui.label(status);
+            }
 
             ui.add_space(20.0);
             if ui.button("Restart").clicked() {
-                *self = MathQuizApp::default(); // Reset the game state
+                self.restart();
             }
         });
     }
 
-    fn process_input(&mut self) {
-        if let Ok(user_answer) = self.user_input.trim().parse::<i32>() {
-            if user_answer == self.answer {
-                self.correct_answers += 1;
+    fn display_stats(&self, ui: &mut egui::Ui) {
+        let Some(stats) = SessionStats::from_history(self.session.history()) else {
+            return;
+        };
 
-                // Adjust score and timer based on question type
-                if self.is_pemdas {
-                    self.score += 2; // PEMDAS questions count as 2 points
-                } else {
-                    self.score += 1;
+        egui::CollapsingHeader::new("Statistics").show(ui, |ui| {
+            ui.label(format!(
+                "Average time: {:.1} s",
+                stats.average_time.as_secs_f64()
+            ));
+            ui.label(format!(
+                "Median time: {:.1} s",
+                stats.median_time.as_secs_f64()
+            ));
+
+            ui.add_space(10.0);
+            ui.strong("Slowest questions");
+            for record in &stats.slowest {
+                ui.label(format!(
+                    "{} → {}  ({:.1} s)",
+                    record.question,
+                    record.expected,
+                    record.latency.as_secs_f64()
+                ));
+            }
+
+            ui.add_space(10.0);
+            ui.strong("Accuracy by operation");
+            egui::Grid::new("accuracy").striped(true).show(ui, |ui| {
+                for (kind, accuracy) in &stats.accuracy_by_kind {
+                    ui.label(kind.label());
+                    ui.label(format!("{}/{}", accuracy.correct, accuracy.total));
+                    ui.label(format!("{:.0}%", accuracy.ratio() * 100.0));
+                    ui.end_row();
                 }
+            });
+        });
+    }
 
-                self.remaining_time += Duration::new(1, 0); // Add 1 second for correct answer
-                self.feedback = "Correct!".to_string();
-            } else {
-                self.wrong_answers += 1;
-                // Subtract 2 seconds for wrong answer on top of the normal countdown
-                self.remaining_time = self
-                    .remaining_time
-                    .checked_sub(Duration::new(2, 0))
-                    .unwrap_or(Duration::new(0, 0));
-                self.feedback = format!("Wrong! The correct answer was {}.", self.answer);
-            }
-
-            // Generate new question
-            let (new_question, new_answer, is_pemdas) = generate_problem(self.score);
-            self.question = new_question;
-            self.answer = new_answer;
-            self.is_pemdas = is_pemdas;
-        } else {
-            self.wrong_answers += 1;
-            // Subtract 2 seconds for invalid input
-            self.remaining_time = self
-                .remaining_time
-                .checked_sub(Duration::new(2, 0))
-                .unwrap_or(Duration::new(0, 0));
-            self.feedback = "Invalid input. Try again!".to_string();
+    fn display_leaderboard(&self, ui: &mut egui::Ui) {
+        let (scores, placement) = match &self.leaderboard {
+            Some(Ok((scores, placement))) => (scores, placement),
+            Some(Err(message)) => {
+                ui.label(message);
+                return;
+            }
+            None => return,
+        };
+
+        if placement.personal_best {
+            ui.colored_label(egui::Color32::GOLD, "New personal best!");
         }
 
+        ui.heading("High Scores");
+        if let Some(questions) = placement.questions {
+            ui.label(format!("Sprints of {} questions", questions));
+        }
+        egui::Grid::new("leaderboard").striped(true).show(ui, |ui| {
+            let result = match self.format {
+                GameFormat::Sprint => "Time",
+                _ => "Score",
+            };
+            for header in ["#", result, "Correct", "Wrong", "Date", "Mode"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+
+            for (rank, entry) in scores
+                .top(placement.questions, LEADERBOARD_SIZE)
+                .into_iter()
+                .enumerate()
+            {
+                let color = if placement.rank == Some(rank) {
+                    egui::Color32::GOLD
+                } else {
+                    ui.visuals().text_color()
+                };
+                let cells = [
+                    (rank + 1).to_string(),
+                    match entry.time_ms {
+                        Some(time_ms) => format!("{:.1} s", time_ms as f64 / 1000.0),
+                        None => entry.score.to_string(),
+                    },
+                    entry.correct_answers.to_string(),
+                    entry.wrong_answers.to_string(),
+                    entry.date.format("%Y-%m-%d").to_string(),
+                    entry.mode.clone(),
+                ];
+                for cell in cells {
+                    ui.colored_label(color, cell);
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    fn process_input(&mut self) {
+        let outcome = self.session.submit(&self.user_input);
+        self.show_outcome(outcome);
+
         // Clear user input
         self.user_input.clear();
     }
-}
 
-fn generate_problem(score: i32) -> (String, i32, bool) {
-    let mut rng = rand::thread_rng();
+    fn show_outcome(&mut self, outcome: Option<SubmitOutcome>) {
+        if let Some(outcome) = outcome {
+            self.feedback = outcome.to_string();
+        }
+    }
+}
 
-    // Adjust difficulty based on score
-    let (min, max, include_complex_ops) = if score < 5 {
-        (1, 10, false) // Easy: Numbers 1–10, no PEMDAS
-    } else if score < 10 {
-        (1, 20, true) // Medium: Numbers 1–20, occasional PEMDAS
-    } else {
-        (1, 50, true) // Hard: Numbers 1–50, frequent PEMDAS
-    };
+/// Command-line options shared by both front ends.
+#[derive(Default)]
+struct Args {
+    seed: Option<u64>,
+    config: Option<PathBuf>,
+    mode: PracticeMode,
+    format: GameFormat,
+    problems: Option<PathBuf>,
+    export: Option<PathBuf>,
+    tui: bool,
+}
 
-    let num1 = rng.gen_range(min..=max);
-    let num2 = rng.gen_range(min..=max);
-    let num3 = rng.gen_range(min..=max);
-
-    if include_complex_ops && rng.gen_bool(0.3) {
-        // 30% chance to generate PEMDAS question
-        let operator = rng.gen_range(0..2); // 0: *, 1: /
-        match operator {
-            0 => (
-                format!("{} * ({} + {})", num1, num2, num3),
-                num1 * (num2 + num3),
-                true, // PEMDAS question
-            ),
-            1 => (
-                format!("({} - {}) / {}", num1 + num3, num2, num3),
-                if num3 != 0 {
-                    (num1 + num3 - num2) / num3
-                } else {
-                    0
-                },
-                true,
-            ),
-            _ => unreachable!(),
-        }
-    } else {
-        // Simple operations
-        let operator = rng.gen_range(0..4); // 0: +, 1: -, 2: *, 3: /
-        match operator {
-            0 => (format!("{} + {}", num1, num2), num1 + num2, false),
-            1 => (format!("{} - {}", num1, num2), num1 - num2, false),
-            2 => (format!("{} * {}", num1, num2), num1 * num2, false),
-            3 => {
-                if num2 != 0 {
-                    (format!("{} / {}", num1 * num2, num2), num1, false)
-                } else {
-                    (format!("{} + {}", num1, 1), num1 + 1, false)
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = args.next().ok_or("--seed requires a value")?;
+                    parsed.seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid seed: {}", value))?,
+                    );
                 }
+                "--config" => {
+                    let value = args.next().ok_or("--config requires a path")?;
+                    parsed.config = Some(PathBuf::from(value));
+                }
+                "--mode" => {
+                    let value = args.next().ok_or("--mode requires a value")?;
+                    parsed.mode = value.parse()?;
+                }
+                "--format" => {
+                    let value = args.next().ok_or("--format requires a value")?;
+                    parsed.format = value.parse()?;
+                }
+                "--problems" => {
+                    let value = args.next().ok_or("--problems requires a path")?;
+                    parsed.problems = Some(PathBuf::from(value));
+                }
+                "--export" => {
+                    let value = args.next().ok_or("--export requires a path")?;
+                    let path = PathBuf::from(value);
+                    if ExportFormat::from_path(&path).is_none() {
+                        return Err(format!(
+                            "cannot export to {}: use a .csv or .json file",
+                            path.display()
+                        ));
+                    }
+                    parsed.export = Some(path);
+                }
+                "--tui" => parsed.tui = true,
+                _ => return Err(format!("unknown argument: {}", arg)),
             }
-            _ => unreachable!(),
         }
+        Ok(parsed)
     }
 }
 
+fn new_session(
+    config: &GameConfig,
+    seed_override: Option<u64>,
+    mode: PracticeMode,
+    format: GameFormat,
+    problem_set: Option<&ProblemSet>,
+) -> GameSession {
+    let mut generator = match seed_override.or(config.seed) {
+        Some(seed) => ProblemGenerator::new(seed),
+        None => ProblemGenerator::from_entropy(),
+    }
+    .with_mode(mode);
+    if let (PracticeMode::Custom, Some(set)) = (mode, problem_set) {
+        generator = generator.with_problems(set.to_problems());
+    }
+    let session = GameSession::new(config.clone(), generator).with_format(format);
+    if mode != PracticeMode::Review {
+        return session;
+    }
+
+    let deck = match ReviewDeck::default_path().map(|path| ReviewDeck::load(&path)) {
+        Some(Ok(deck)) => deck,
+        Some(Err(err)) => {
+            eprintln!("Could not load review deck: {}", err);
+            ReviewDeck::default()
+        }
+        None => ReviewDeck::default(),
+    };
+    let due = deck.due(Utc::now()).into_iter().map(|card| card.problem());
+    session.with_review(due.collect())
+}
+
+/// Reschedules missed and reviewed facts after a game.
+fn save_review(session: &GameSession) -> Result<(), String> {
+    let path = ReviewDeck::default_path().ok_or("No data directory to save the review deck in")?;
+    ReviewDeck::record_at(&path, session.history(), Utc::now())
+        .map_err(|err| format!("Could not save review deck: {}", err))
+}
+
 fn main() -> eframe::Result<()> {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            eprintln!("usage: rapid_math [--seed <N>] [--config <PATH>] [--mode <MODE>] [--format <FORMAT>] [--problems <FILE>] [--export <FILE>] [--tui]");
+            std::process::exit(2);
+        }
+    };
+
+    // An explicit --config must exist; the default location is optional
+    let config_path = args.config.clone().or_else(GameConfig::default_path);
+    let config = match (&args.config, &config_path) {
+        (Some(path), _) => GameConfig::load(path),
+        (None, Some(path)) => GameConfig::load_or_default(path),
+        (None, None) => Ok(GameConfig::default()),
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+
+    // A problem set replaces the generated problems entirely
+    let problem_set = match &args.problems {
+        Some(path) => match ProblemSet::load(path) {
+            Ok(set) => Some(set),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(2);
+            }
+        },
+        None => None,
+    };
+    let mode = if problem_set.is_some() {
+        PracticeMode::Custom
+    } else {
+        args.mode
+    };
+
+    if args.tui {
+        let session = new_session(&config, args.seed, mode, args.format, problem_set.as_ref());
+        if let Err(err) = tui::run(session, args.export) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let options = eframe::NativeOptions {
         initial_window_size: Some(egui::vec2(400.0, 600.0)),
         ..Default::default()
@@ -228,6 +913,14 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "Math Quiz",
         options,
-        Box::new(|_cc| Box::new(MathQuizApp::default())),
+        Box::new(move |_cc| {
+            Box::new(MathQuizApp::new(
+                config,
+                config_path,
+                mode,
+                problem_set,
+                args,
+            ))
+        }),
     )
 }
@@ -0,0 +1,362 @@
+use crate::answer::Answer;
+use crate::expr::Expr;
+use crate::generator::{Problem, ProblemKind};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// One question of a custom set, as written in the file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomProblem {
+    pub question: String,
+    /// The answer as a player would type it: `12`, `3/4` or `2.5`.
+    pub answer: String,
+    /// Points for answering correctly.
+    #[serde(default = "default_points")]
+    pub points: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+fn default_points() -> i32 {
+    1
+}
+
+impl CustomProblem {
+    /// Describes what is wrong with the problem, if anything.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.question.trim().is_empty() {
+            return Err("the question is empty".to_string());
+        }
+        let Ok(answer) = self.answer.parse::<Answer>() else {
+            return Err(format!(
+                "'{}' is not a number, fraction or decimal",
+                self.answer
+            ));
+        };
+        if let Some(expr) = self.expression() {
+            match expr.eval_exact() {
+                Ok(value) if value == answer.value() => {}
+                Ok(value) => {
+                    return Err(format!("{} is {}, not {}", expr, value, self.answer.trim()));
+                }
+                Err(err) => return Err(format!("{} can't be worked out: {}", expr, err)),
+            }
+        }
+        if self.points < 1 {
+            return Err("points must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// The question as an expression, if it is plain arithmetic, optionally
+    /// ending in `= ?`. Only these questions are checked against their
+    /// answer; anything else is free text.
+    pub fn expression(&self) -> Option<Expr> {
+        let question = self.question.trim();
+        let question = question.strip_suffix("= ?").unwrap_or(question);
+        question.parse().ok()
+    }
+
+    /// The problem as the session plays it. Call [`CustomProblem::validate`]
+    /// first; an unreadable answer panics.
+    pub fn problem(&self) -> Problem {
+        Problem {
+            question: self.question.trim().to_string(),
+            answer: self.answer.parse().expect("problem was validated"),
+            kind: ProblemKind::Custom,
+            operations: 1,
+            points: Some(self.points),
+        }
+    }
+}
+
+/// The file formats a problem set can be stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProblemSetFormat {
+    /// A `[[problems]]` table per question.
+    Toml,
+    /// `{"problems": [...]}`, with the same fields as TOML.
+    Json,
+    /// A `question,answer,points,tags` header, tags separated by `;`.
+    Csv,
+}
+
+impl ProblemSetFormat {
+    /// Picks the format from the file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "toml" => Some(ProblemSetFormat::Toml),
+            "json" => Some(ProblemSetFormat::Json),
+            "csv" => Some(ProblemSetFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Why a problem set could not be used.
+#[derive(Debug)]
+pub enum ProblemSetError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    UnknownFormat(PathBuf),
+    Parse {
+        path: PathBuf,
+        message: String,
+    },
+    Empty,
+    /// Problem `index` (counting from 1) is unusable.
+    Invalid {
+        index: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ProblemSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProblemSetError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ProblemSetError::UnknownFormat(path) => write!(
+                f,
+                "{}: problem sets must be .toml, .json or .csv files",
+                path.display()
+            ),
+            ProblemSetError::Parse { path, message } => {
+                write!(
+                    f,
+                    "{} is not a valid problem set: {}",
+                    path.display(),
+                    message
+                )
+            }
+            ProblemSetError::Empty => write!(f, "the problem set has no problems"),
+            ProblemSetError::Invalid { index, message } => {
+                write!(f, "problem {}: {}", index, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProblemSetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProblemSetError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// A row of a CSV problem set, where every column but the first two may be
+/// left empty.
+#[derive(Serialize, Deserialize)]
+struct CsvRow {
+    question: String,
+    answer: String,
+    points: Option<i32>,
+    tags: Option<String>,
+}
+
+/// Questions a teacher wrote, played instead of generated problems.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProblemSet {
+    pub problems: Vec<CustomProblem>,
+}
+
+impl ProblemSet {
+    /// Loads and validates a set, in the format given by its extension.
+    pub fn load(path: &Path) -> Result<Self, ProblemSetError> {
+        let format = ProblemSetFormat::from_path(path)
+            .ok_or_else(|| ProblemSetError::UnknownFormat(path.to_path_buf()))?;
+        let text = fs::read_to_string(path).map_err(|source| ProblemSetError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let set = Self::parse(&text, format).map_err(|message| ProblemSetError::Parse {
+            path: path.to_path_buf(),
+            message,
+        })?;
+        set.validate()?;
+        Ok(set)
+    }
+
+    /// Validates the set and writes it in the format given by the extension.
+    pub fn save(&self, path: &Path) -> Result<(), ProblemSetError> {
+        self.validate()?;
+        let format = ProblemSetFormat::from_path(path)
+            .ok_or_else(|| ProblemSetError::UnknownFormat(path.to_path_buf()))?;
+        let io_error = |source| ProblemSetError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        fs::write(path, self.to_text(format)).map_err(io_error)
+    }
+
+    pub fn to_text(&self, format: ProblemSetFormat) -> String {
+        match format {
+            ProblemSetFormat::Toml => {
+                toml::to_string_pretty(self).expect("problem sets always serialize")
+            }
+            ProblemSetFormat::Json => {
+                serde_json::to_string_pretty(self).expect("problem sets always serialize")
+            }
+            ProblemSetFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                for problem in &self.problems {
+                    let row = CsvRow {
+                        question: problem.question.clone(),
+                        answer: problem.answer.clone(),
+                        points: Some(problem.points),
+                        tags: Some(problem.tags.join("; ")),
+                    };
+                    writer.serialize(row).expect("writing to memory can't fail");
+                }
+                let bytes = writer.into_inner().expect("writing to memory can't fail");
+                String::from_utf8(bytes).expect("CSV of strings is UTF-8")
+            }
+        }
+    }
+
+    pub fn parse(text: &str, format: ProblemSetFormat) -> Result<Self, String> {
+        match format {
+            ProblemSetFormat::Toml => toml::from_str(text).map_err(|err| err.to_string()),
+            ProblemSetFormat::Json => serde_json::from_str(text).map_err(|err| err.to_string()),
+            ProblemSetFormat::Csv => {
+                let mut reader = csv::Reader::from_reader(text.as_bytes());
+                let problems = reader
+                    .deserialize()
+                    .map(|row| {
+                        let row: CsvRow = row.map_err(|err| err.to_string())?;
+                        Ok(CustomProblem {
+                            question: row.question,
+                            answer: row.answer,
+                            points: row.points.unwrap_or_else(default_points),
+                            tags: row
+                                .tags
+                                .iter()
+                                .flat_map(|tags| tags.split(';'))
+                                .map(|tag| tag.trim().to_string())
+                                .filter(|tag| !tag.is_empty())
+                                .collect(),
+                        })
+                    })
+                    .collect::<Result<_, String>>()?;
+                Ok(Self { problems })
+            }
+        }
+    }
+
+    /// Checks the set is playable: at least one problem, each with a
+    /// question, a readable answer and positive points.
+    pub fn validate(&self) -> Result<(), ProblemSetError> {
+        if self.problems.is_empty() {
+            return Err(ProblemSetError::Empty);
+        }
+        for (index, problem) in self.problems.iter().enumerate() {
+            problem
+                .validate()
+                .map_err(|message| ProblemSetError::Invalid {
+                    index: index + 1,
+                    message,
+                })?;
+        }
+        Ok(())
+    }
+
+    /// The playable problems, in file order.
+    pub fn to_problems(&self) -> Vec<Problem> {
+        self.problems.iter().map(CustomProblem::problem).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_parse_to_the_same_set() {
+        let toml = r#"
+            [[problems]]
+            question = "What is 6 * 7?"
+            answer = "42"
+            points = 2
+            tags = ["tables", "week 3"]
+
+            [[problems]]
+            question = "Half of 3"
+            answer = "1 1/2"
+        "#;
+        let json = r#"{"problems": [
+            {"question": "What is 6 * 7?", "answer": "42", "points": 2,
+             "tags": ["tables", "week 3"]},
+            {"question": "Half of 3", "answer": "1 1/2"}
+        ]}"#;
+        let csv = "question,answer,points,tags\n\
+                   What is 6 * 7?,42,2,tables; week 3\n\
+                   Half of 3,1 1/2,,\n";
+
+        let set = ProblemSet::parse(toml, ProblemSetFormat::Toml).unwrap();
+        assert_eq!(
+            ProblemSet::parse(json, ProblemSetFormat::Json),
+            Ok(set.clone())
+        );
+        assert_eq!(
+            ProblemSet::parse(csv, ProblemSetFormat::Csv),
+            Ok(set.clone())
+        );
+        assert!(set.validate().is_ok());
+        for format in [
+            ProblemSetFormat::Toml,
+            ProblemSetFormat::Json,
+            ProblemSetFormat::Csv,
+        ] {
+            let text = set.to_text(format);
+            assert_eq!(ProblemSet::parse(&text, format), Ok(set.clone()));
+        }
+        assert_eq!(set.to_problems()[1].points, Some(1));
+    }
+
+    #[test]
+    fn invalid_problems_are_reported_by_position() {
+        let mut set = ProblemSet {
+            problems: vec![CustomProblem {
+                question: "2 + 2".to_string(),
+                answer: "4".to_string(),
+                points: 1,
+                tags: Vec::new(),
+            }],
+        };
+        set.problems.push(CustomProblem {
+            answer: "four".to_string(),
+            ..set.problems[0].clone()
+        });
+        assert!(matches!(
+            set.validate(),
+            Err(ProblemSetError::Invalid { index: 2, .. })
+        ));
+        set.problems[1].question = "2 + 2 = ?".to_string();
+        set.problems[1].answer = "5".to_string();
+        assert_eq!(
+            set.problems[1].validate(),
+            Err("2 + 2 is 4, not 5".to_string())
+        );
+        set.problems[1].question = "-2.5 + 1".to_string();
+        set.problems[1].answer = "7".to_string();
+        assert!(set.problems[1].validate().is_err());
+        set.problems[1].question = "2 + + 3".to_string();
+        assert_eq!(set.problems[1].validate(), Ok(()));
+        assert_eq!(set.problems[1].expression(), None);
+        assert!(matches!(
+            ProblemSet::default().validate(),
+            Err(ProblemSetError::Empty)
+        ));
+    }
+}
@@ -0,0 +1,334 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// An exact fraction, always stored in lowest terms with a positive
+/// denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: i64,
+    denom: i64,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { numer: 0, denom: 1 };
+
+    /// Returns `None` if `denom` is zero.
+    pub fn new(numer: i64, denom: i64) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        let divisor = gcd(numer, denom) * denom.signum();
+        Some(Self {
+            numer: numer / divisor,
+            denom: denom / divisor,
+        })
+    }
+
+    pub fn integer(n: i64) -> Self {
+        Self { numer: n, denom: 1 }
+    }
+
+    pub fn numer(self) -> i64 {
+        self.numer
+    }
+
+    pub fn denom(self) -> i64 {
+        self.denom
+    }
+
+    pub fn is_integer(self) -> bool {
+        self.denom == 1
+    }
+
+    /// The fewest decimal places that show the value exactly, or `None` if
+    /// it repeats forever (like 1/3).
+    pub fn decimal_places(self) -> Option<u32> {
+        let mut denom = self.denom;
+        let (mut twos, mut fives) = (0, 0);
+        while denom % 2 == 0 {
+            denom /= 2;
+            twos += 1;
+        }
+        while denom % 5 == 0 {
+            denom /= 5;
+            fives += 1;
+        }
+        (denom == 1).then_some(twos.max(fives))
+    }
+
+    /// Formats the value with exactly `places` decimals, rounding half away
+    /// from zero.
+    pub fn to_decimal_string(self, places: u32) -> String {
+        let scale = 10i128.pow(places);
+        let scaled = i128::from(self.numer) * scale;
+        let denom = i128::from(self.denom);
+        let rounded = (scaled.abs() * 2 + denom) / (denom * 2);
+        let sign = if scaled < 0 && rounded != 0 { "-" } else { "" };
+        if places == 0 {
+            return format!("{}{}", sign, rounded);
+        }
+        format!(
+            "{}{}.{:0width$}",
+            sign,
+            rounded / scale,
+            rounded % scale,
+            width = places as usize
+        )
+    }
+
+    /// The exact square root, or `None` if the value is negative or not the
+    /// square of a fraction.
+    pub fn checked_sqrt(self) -> Option<Self> {
+        Some(Self {
+            numer: exact_sqrt(self.numer)?,
+            denom: exact_sqrt(self.denom)?,
+        })
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let numer = self
+            .numer
+            .checked_mul(other.denom)?
+            .checked_add(other.numer.checked_mul(self.denom)?)?;
+        Self::new(numer, self.denom.checked_mul(other.denom)?)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(Self {
+            numer: other.numer.checked_neg()?,
+            denom: other.denom,
+        })
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        Self::new(
+            self.numer.checked_mul(other.numer)?,
+            self.denom.checked_mul(other.denom)?,
+        )
+    }
+
+    /// Returns `None` on overflow or division by zero.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        Self::new(
+            self.numer.checked_mul(other.denom)?,
+            self.denom.checked_mul(other.numer)?,
+        )
+    }
+}
+
+impl From<i32> for Rational {
+    fn from(n: i32) -> Self {
+        Self::integer(n.into())
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Denominators are positive, so cross-multiplying keeps the order
+        let lhs = i128::from(self.numer) * i128::from(other.denom);
+        let rhs = i128::from(other.numer) * i128::from(self.denom);
+        lhs.cmp(&rhs)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+/// A fraction as the player typed it, before reducing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrittenFraction {
+    pub value: Rational,
+    /// The fractional part was already in lowest terms and, for a mixed
+    /// number, proper.
+    pub in_lowest_terms: bool,
+}
+
+impl FromStr for WrittenFraction {
+    type Err = ();
+
+    /// Accepts integers (`3`), fractions (`3/4`, `-6/8`) and mixed numbers
+    /// (`1 1/2`, `-1 1/2`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, s),
+        };
+        let parse_part = |part: &str| -> Result<i64, ()> {
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(());
+            }
+            part.parse().map_err(|_| ())
+        };
+
+        let (whole, fraction) = match unsigned.split_once(char::is_whitespace) {
+            Some((whole, fraction)) => (Some(parse_part(whole)?), fraction.trim()),
+            None => (None, unsigned),
+        };
+        let (numer, denom, mixed_is_proper) = match fraction.split_once('/') {
+            Some((numer, denom)) => {
+                let (numer, denom) = (parse_part(numer.trim())?, parse_part(denom.trim())?);
+                (numer, denom, numer < denom)
+            }
+            // A bare integer can't follow a whole part
+            None if whole.is_none() => (parse_part(fraction)?, 1, true),
+            None => return Err(()),
+        };
+
+        let fraction = Rational::new(numer, denom).ok_or(())?;
+        let mut value = match whole {
+            Some(whole) => Rational::integer(whole).checked_add(fraction).ok_or(())?,
+            None => fraction,
+        };
+        if negative {
+            value = Rational::ZERO.checked_sub(value).ok_or(())?;
+        }
+        Ok(Self {
+            value,
+            in_lowest_terms: gcd(numer, denom) == 1 && (whole.is_none() || mixed_is_proper),
+        })
+    }
+}
+
+/// A decimal number as the player typed it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrittenDecimal {
+    pub value: Rational,
+    /// Digits written after the decimal point.
+    pub places: u32,
+}
+
+impl FromStr for WrittenDecimal {
+    type Err = ();
+
+    /// Accepts `12`, `-0.75`, `.5` and `2.50`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !all_digits(whole) || !all_digits(fraction)
+        {
+            return Err(());
+        }
+
+        let places = fraction.len() as u32;
+        let digits: i64 = format!("{}{}", whole, fraction).parse().map_err(|_| ())?;
+        let scale = 10i64.checked_pow(places).ok_or(())?;
+        let value = Rational::new(if negative { -digits } else { digits }, scale).ok_or(())?;
+        Ok(Self { value, places })
+    }
+}
+
+impl FromStr for Rational {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<WrittenFraction>().map(|written| written.value)
+    }
+}
+
+// Stored as text ("3/4") so saved files stay readable
+impl Serialize for Rational {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rational {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse()
+            .map_err(|_| serde::de::Error::custom(format!("invalid fraction: {}", text)))
+    }
+}
+
+fn exact_sqrt(n: i64) -> Option<i64> {
+    if n < 0 {
+        return None;
+    }
+    // The float estimate can be off by one for large values
+    let estimate = (n as f64).sqrt() as i64;
+    (estimate.saturating_sub(1)..=estimate + 1).find(|root| root.checked_mul(*root) == Some(n))
+}
+
+fn gcd(mut a: i64, mut b: i64) -> i64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs().max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(s: &str) -> WrittenFraction {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_fractions_and_mixed_numbers() {
+        assert_eq!(written("3/4").value, Rational::new(3, 4).unwrap());
+        assert_eq!(written("-1 1/2").value, Rational::new(-3, 2).unwrap());
+        assert_eq!(written(" 5 ").value, Rational::integer(5));
+        assert!(written("3/4").in_lowest_terms);
+        assert!(!written("6/8").in_lowest_terms);
+        assert!(!written("1 3/2").in_lowest_terms);
+        for bad in ["", "3/", "/4", "1/0", "1 2", "a/b", "--1"] {
+            assert!(bad.parse::<WrittenFraction>().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn parses_and_formats_decimals() {
+        let written: WrittenDecimal = "-2.50".parse().unwrap();
+        assert_eq!(written.value, Rational::new(-5, 2).unwrap());
+        assert_eq!(written.places, 2);
+        assert_eq!(".5".parse::<WrittenDecimal>().unwrap().places, 1);
+        for bad in ["", ".", "1.2.3", "1,5", "- 1"] {
+            assert!(bad.parse::<WrittenDecimal>().is_err(), "{:?}", bad);
+        }
+
+        let value = Rational::new(-5, 2).unwrap();
+        assert_eq!(value.decimal_places(), Some(1));
+        assert_eq!(value.to_decimal_string(2), "-2.50");
+        assert_eq!(Rational::new(1, 3).unwrap().decimal_places(), None);
+        assert_eq!(Rational::new(2, 3).unwrap().to_decimal_string(2), "0.67");
+    }
+
+    #[test]
+    fn arithmetic_stays_in_lowest_terms() {
+        let half = Rational::new(1, 2).unwrap();
+        let quarter = Rational::new(-2, -8).unwrap();
+        assert_eq!(quarter, Rational::new(1, 4).unwrap());
+        assert_eq!(half.checked_add(quarter), Rational::new(3, 4));
+        assert_eq!(half.checked_div(quarter), Some(Rational::integer(2)));
+        assert_eq!(half.checked_div(Rational::ZERO), None);
+        assert_eq!(quarter.checked_sqrt(), Some(half));
+        assert_eq!(half.checked_sqrt(), None);
+        assert_eq!(Rational::new(3, -6).unwrap().to_string(), "-1/2");
+    }
+}
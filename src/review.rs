@@ -0,0 +1,179 @@
+use crate::answer::Answer;
+use crate::expr::Expr;
+use crate::generator::{Problem, ProblemKind};
+use crate::session::QuestionRecord;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Days until a card in each Leitner box is due again. A card answered
+/// correctly moves up one box; past the last box it has been learned and
+/// leaves the deck. A miss sends it back to the first box.
+const BOX_INTERVAL_DAYS: [i64; 5] = [0, 1, 3, 7, 14];
+
+/// A missed fact waiting to be practiced again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewCard {
+    pub question: String,
+    pub answer: Answer,
+    pub kind: ProblemKind,
+    /// Index into the Leitner boxes, 0 being the most frequent.
+    pub leitner_box: usize,
+    pub due: DateTime<Utc>,
+}
+
+impl ReviewCard {
+    pub fn problem(&self) -> Problem {
+        Problem {
+            question: self.question.clone(),
+            answer: self.answer.clone(),
+            kind: self.kind,
+            // Cards don't store this, but expression questions can recount it
+            operations: self
+                .question
+                .parse::<Expr>()
+                .map_or(1, |expr| expr.operation_count()),
+            points: None,
+        }
+    }
+}
+
+/// Missed facts scheduled with Leitner boxes, stored as JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReviewDeck {
+    cards: Vec<ReviewCard>,
+}
+
+impl ReviewDeck {
+    /// `<platform data dir>/rapid_math/review.json`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("rapid_math").join("review.json"))
+    }
+
+    /// Loads the deck, treating a missing file as an empty one.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, json)
+    }
+
+    /// Loads the deck at `path`, applies a finished session's answers and
+    /// writes it back.
+    pub fn record_at(
+        path: &Path,
+        history: &[QuestionRecord],
+        now: DateTime<Utc>,
+    ) -> io::Result<()> {
+        let mut deck = Self::load(path)?;
+        deck.apply(history, now);
+        deck.save(path)
+    }
+
+    /// Reschedules cards from a session's answers: misses enter (or return
+    /// to) the first box and correct answers to known cards move them up.
+    pub fn apply(&mut self, history: &[QuestionRecord], now: DateTime<Utc>) {
+        for record in history {
+            let position = self
+                .cards
+                .iter()
+                .position(|c| c.question == record.question);
+            match (position, record.correct) {
+                (Some(i), true) => {
+                    let card = &mut self.cards[i];
+                    card.leitner_box += 1;
+                    match BOX_INTERVAL_DAYS.get(card.leitner_box) {
+                        Some(&days) => card.due = now + Duration::days(days),
+                        None => {
+                            self.cards.remove(i);
+                        }
+                    }
+                }
+                (Some(i), false) => {
+                    let card = &mut self.cards[i];
+                    card.leitner_box = 0;
+                    card.due = now;
+                }
+                (None, false) => self.cards.push(ReviewCard {
+                    question: record.question.clone(),
+                    answer: record.expected.clone(),
+                    kind: record.kind,
+                    leitner_box: 0,
+                    due: now,
+                }),
+                (None, true) => {}
+            }
+        }
+    }
+
+    /// Cards due at `now`, most overdue first.
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<&ReviewCard> {
+        let mut due: Vec<_> = self.cards.iter().filter(|c| c.due <= now).collect();
+        due.sort_by_key(|c| c.due);
+        due
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(question: &str, correct: bool) -> QuestionRecord {
+        QuestionRecord {
+            question: question.to_string(),
+            expected: Answer::Integer(12),
+            given: String::new(),
+            correct,
+            latency: std::time::Duration::ZERO,
+            kind: ProblemKind::Multiplication,
+        }
+    }
+
+    #[test]
+    fn cards_climb_the_boxes_until_learned() {
+        let now = Utc::now();
+        let mut deck = ReviewDeck::default();
+        deck.apply(&[answer("3 * 4", false), answer("1 + 1", true)], now);
+        assert_eq!(deck.len(), 1);
+        assert_eq!(deck.due(now).len(), 1);
+
+        deck.apply(&[answer("3 * 4", true)], now);
+        assert!(deck.due(now).is_empty());
+        assert_eq!(deck.due(now + Duration::days(1)).len(), 1);
+
+        for _ in 1..BOX_INTERVAL_DAYS.len() {
+            deck.apply(&[answer("3 * 4", true)], now);
+        }
+        assert!(deck.is_empty());
+    }
+
+    #[test]
+    fn a_miss_sends_the_card_back_to_the_first_box() {
+        let now = Utc::now();
+        let mut deck = ReviewDeck::default();
+        deck.apply(&[answer("3 * 4", false), answer("3 * 4", true)], now);
+        deck.apply(&[answer("3 * 4", false)], now);
+        assert_eq!(deck.due(now)[0].leitner_box, 0);
+    }
+}
@@ -0,0 +1,813 @@
+use crate::adaptive::AdaptiveDifficulty;
+use crate::answer::Answer;
+use crate::choices;
+use crate::config::GameConfig;
+use crate::generator::{Difficulty, PracticeMode, Problem, ProblemGenerator, ProblemKind};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// How a game is won or lost, independent of which problems it asks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameFormat {
+    /// Score as much as possible before the clock runs out.
+    #[default]
+    Timed,
+    /// Answer a fixed number of questions correctly as fast as possible.
+    /// Wrong answers add the penalty to the time instead of taking it away.
+    Sprint,
+    /// No clock: every wrong answer costs a life and the problems get
+    /// harder as the game goes on.
+    Survival,
+}
+
+impl GameFormat {
+    pub const ALL: [GameFormat; 3] = [GameFormat::Timed, GameFormat::Sprint, GameFormat::Survival];
+
+    /// Short identifier used on the command line and in saved scores.
+    pub fn name(self) -> &'static str {
+        match self {
+            GameFormat::Timed => "timed",
+            GameFormat::Sprint => "sprint",
+            GameFormat::Survival => "survival",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameFormat::Timed => "Beat the clock",
+            GameFormat::Sprint => "Sprint",
+            GameFormat::Survival => "Survival",
+        }
+    }
+
+    /// Whether the game ends when a countdown reaches zero.
+    pub fn has_clock(self) -> bool {
+        match self {
+            GameFormat::Timed => true,
+            GameFormat::Sprint | GameFormat::Survival => false,
+        }
+    }
+}
+
+impl FromStr for GameFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|format| format.name()).collect();
+                format!(
+                    "unknown format '{}', expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// What happened to a submitted answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubmitOutcome {
+    Correct,
+    Wrong {
+        expected: Answer,
+    },
+    /// An estimate close enough for some points, but not exact.
+    Close {
+        points: i32,
+        expected: Answer,
+    },
+    /// The input was not a well-formed answer. It still counts as a wrong
+    /// answer, but the question stays the same.
+    Invalid,
+    /// The question's own countdown ran out, which counts as a wrong answer.
+    TimedOut {
+        expected: Answer,
+    },
+}
+
+/// The feedback shown to the player.
+impl fmt::Display for SubmitOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubmitOutcome::Correct => write!(f, "Correct!"),
+            SubmitOutcome::Wrong { expected } => {
+                write!(f, "Wrong! The correct answer was {}.", expected)
+            }
+            SubmitOutcome::Close { points, expected } => {
+                write!(f, "Close enough! +{} (exactly {}).", points, expected)
+            }
+            SubmitOutcome::Invalid => write!(f, "Invalid input. Try again!"),
+            SubmitOutcome::TimedOut { expected } => {
+                write!(f, "Time's up! The correct answer was {}.", expected)
+            }
+        }
+    }
+}
+
+/// One submitted answer and how long the player took to give it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionRecord {
+    pub question: String,
+    pub expected: Answer,
+    /// The input exactly as typed, trimmed.
+    pub given: String,
+    pub correct: bool,
+    pub latency: Duration,
+    pub kind: ProblemKind,
+}
+
+/// The rules of a timed quiz, independent of any front end.
+///
+/// The session never reads the clock itself: callers drive it with
+/// [`GameSession::tick`] and [`GameSession::submit`], which keeps it
+/// deterministic and easy to test.
+pub struct GameSession {
+    config: GameConfig,
+    format: GameFormat,
+    generator: ProblemGenerator,
+    adaptive: AdaptiveDifficulty,
+    problem: Problem,
+    score: i32,
+    /// Part of `score` that came from speed bonuses.
+    speed_points: i32,
+    correct_answers: i32,
+    wrong_answers: i32,
+    remaining_time: Duration,
+    last_tick: Option<Instant>,
+    game_over: bool,
+    /// Total time played so far, as seen through `tick`.
+    elapsed: Duration,
+    /// Penalties added to a sprint's time.
+    penalty: Duration,
+    /// Lives left in survival.
+    lives: u32,
+    /// Correct answers in a row.
+    streak: u32,
+    /// Time left on the current question when `question_timer` is on.
+    question_time_left: Option<Duration>,
+    /// Value of `elapsed` when the current problem was first shown.
+    problem_shown_at: Duration,
+    history: Vec<QuestionRecord>,
+    /// Due review cards not asked yet, used in review mode.
+    review_queue: VecDeque<Problem>,
+    /// Options for the current problem in multiple-choice play, else empty.
+    choices: Vec<Answer>,
+    /// Kept apart from the generator so choices don't change the problems a
+    /// seed produces.
+    choice_rng: StdRng,
+}
+
+impl GameSession {
+    pub fn new(config: GameConfig, generator: ProblemGenerator) -> Self {
+        let mut generator = generator.with_pemdas_depth(config.pemdas_depth);
+        let adaptive = AdaptiveDifficulty::default();
+        let problem = generator.next_problem(&difficulty(&config, &adaptive, generator.mode(), 0));
+        let choice_rng = StdRng::seed_from_u64(generator.seed());
+        let mut session = Self {
+            remaining_time: config.starting_time(),
+            lives: config.survival_lives,
+            question_time_left: config.question_timer.then(|| config.question_time(0)),
+            config,
+            format: GameFormat::default(),
+            generator,
+            adaptive,
+            problem,
+            score: 0,
+            speed_points: 0,
+            correct_answers: 0,
+            wrong_answers: 0,
+            last_tick: None,
+            game_over: false,
+            elapsed: Duration::ZERO,
+            penalty: Duration::ZERO,
+            streak: 0,
+            problem_shown_at: Duration::ZERO,
+            history: Vec::new(),
+            review_queue: VecDeque::new(),
+            choices: Vec::new(),
+            choice_rng,
+        };
+        session.deal_choices();
+        session
+    }
+
+    pub fn with_format(mut self, format: GameFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Supplies the facts that review mode interleaves with generated
+    /// problems, most urgent first. The first one is asked right away.
+    pub fn with_review(mut self, due: Vec<Problem>) -> Self {
+        if self.mode() == PracticeMode::Review {
+            self.review_queue = due.into();
+            if let Some(problem) = self.review_queue.pop_front() {
+                self.problem = problem;
+                self.deal_choices();
+            }
+        }
+        self
+    }
+
+    /// Starts the countdown. Does nothing if the game already started.
+    pub fn start(&mut self, now: Instant) {
+        if self.last_tick.is_none() && !self.game_over {
+            self.last_tick = Some(now);
+        }
+    }
+
+    /// Advances the countdowns to `now`, ending the game when time runs out.
+    ///
+    /// Returns [`SubmitOutcome::TimedOut`] if the last question asked up to
+    /// `now` ran out of time.
+    pub fn tick(&mut self, now: Instant) -> Option<SubmitOutcome> {
+        let last_tick = self.last_tick?;
+        self.last_tick = Some(now);
+        let mut elapsed = now.saturating_duration_since(last_tick);
+        let mut timed_out = None;
+        // One long tick can run out several questions in turn
+        loop {
+            let mut step = elapsed;
+            if self.format.has_clock() {
+                step = step.min(self.remaining_time);
+            }
+            if let Some(left) = self.question_time_left {
+                step = step.min(left);
+            }
+            elapsed -= step;
+            self.elapsed += step;
+            if self.format.has_clock() {
+                self.remaining_time -= step;
+                if self.remaining_time.is_zero() {
+                    self.end();
+                    return timed_out;
+                }
+            }
+            let Some(left) = &mut self.question_time_left else {
+                return timed_out;
+            };
+            *left -= step;
+            if !left.is_zero() {
+                return timed_out;
+            }
+            timed_out = Some(SubmitOutcome::TimedOut {
+                expected: self.problem.answer.clone(),
+            });
+            self.wrong_answers += 1;
+            self.apply_penalty();
+            self.record("", false);
+            self.advance();
+            if self.game_over {
+                return timed_out;
+            }
+        }
+    }
+
+    /// Checks `input` against the current problem and applies the scoring
+    /// rules. Returns `None` if the game is not running.
+    ///
+    /// The answer's latency is measured up to the most recent `tick`, so
+    /// callers should tick right before submitting.
+    pub fn submit(&mut self, input: &str) -> Option<SubmitOutcome> {
+        if !self.is_running() {
+            return None;
+        }
+
+        let rules = self.config.answer_rules();
+        let answer = &self.problem.answer;
+        let (points, exact) = match answer {
+            Answer::Estimate { .. } => match answer.percent_off(input) {
+                Some(off) => (Some(self.config.estimation_points(off)), off == 0.0),
+                None => (None, false),
+            },
+            _ => match answer.check(input, &rules) {
+                Some(correct) => (Some(if correct { self.full_points() } else { 0 }), correct),
+                None => (None, false),
+            },
+        };
+        let Some(points) = points else {
+            self.wrong_answers += 1;
+            self.apply_penalty();
+            self.record(input, false);
+            if self.lives == 0 {
+                self.end();
+            }
+            return Some(SubmitOutcome::Invalid);
+        };
+
+        let outcome = if points > 0 {
+            let bonus = self.speed_bonus();
+            let points = points * self.multiplier() + bonus;
+            self.correct_answers += 1;
+            self.score += points;
+            self.speed_points += bonus;
+            self.remaining_time = self
+                .remaining_time
+                .saturating_add(self.config.correct_bonus());
+            if exact {
+                SubmitOutcome::Correct
+            } else {
+                SubmitOutcome::Close {
+                    points,
+                    expected: self.problem.answer.clone(),
+                }
+            }
+        } else {
+            self.wrong_answers += 1;
+            self.apply_penalty();
+            SubmitOutcome::Wrong {
+                expected: self.problem.answer.clone(),
+            }
+        };
+
+        self.record(input, points > 0);
+        self.advance();
+        Some(outcome)
+    }
+
+    /// Ends the game if this answer finished it, else moves on to the next
+    /// problem.
+    fn advance(&mut self) {
+        let sprint_done = self.format == GameFormat::Sprint
+            && self.correct_answers >= self.config.sprint_questions as i32;
+        if sprint_done || self.lives == 0 {
+            self.end();
+            return;
+        }
+        self.problem = self.next_problem();
+        self.problem_shown_at = self.elapsed;
+        if self.config.question_timer {
+            self.question_time_left = Some(self.question_time());
+        }
+        self.deal_choices();
+    }
+
+    /// Points for a correct answer to the current problem.
+    fn full_points(&self) -> i32 {
+        if let Some(points) = self.problem.points {
+            points
+        } else if self.problem.is_pemdas() {
+            self.config.pemdas_points * self.problem.operations as i32
+        } else {
+            1
+        }
+    }
+
+    /// Extra points for answering the current problem quickly.
+    fn speed_bonus(&self) -> i32 {
+        self.config
+            .speed_bonus(self.elapsed - self.problem_shown_at)
+    }
+
+    /// Submits the option at `index` of [`GameSession::choices`]. Returns
+    /// `None` if the game is not running or there is no such option.
+    pub fn submit_choice(&mut self, index: usize) -> Option<SubmitOutcome> {
+        let choice = self.choices.get(index)?.to_string();
+        self.submit(&choice)
+    }
+
+    fn deal_choices(&mut self) {
+        if self.config.multiple_choice {
+            self.choices = choices::choices(
+                &self.problem,
+                &self.config.estimation_bands,
+                &mut self.choice_rng,
+            );
+        }
+    }
+
+    /// Alternates review cards with generated problems while any are left.
+    fn next_problem(&mut self) -> Problem {
+        let answered = self.correct_answers + self.wrong_answers;
+        if answered % 2 == 0 {
+            if let Some(problem) = self.review_queue.pop_front() {
+                return problem;
+            }
+        }
+        self.generator.next_problem(&self.difficulty())
+    }
+
+    fn record(&mut self, input: &str, correct: bool) {
+        let latency = self.elapsed - self.problem_shown_at;
+        self.streak = if correct { self.streak + 1 } else { 0 };
+        // With a fixed seed, players who get the same questions right see the
+        // same problems however fast they are
+        let rated_latency = if self.generator.has_fixed_seed() {
+            Duration::ZERO
+        } else {
+            latency
+        };
+        self.adaptive
+            .record(self.problem.kind, correct, rated_latency);
+        self.history.push(QuestionRecord {
+            question: self.problem.question.clone(),
+            expected: self.problem.answer.clone(),
+            given: input.trim().to_string(),
+            correct,
+            latency,
+            kind: self.problem.kind,
+        });
+    }
+
+    /// The difficulty the next problem is generated at. Survival climbs a
+    /// level every few questions whatever the score. Score thresholds leave
+    /// out speed bonuses, so timing only matters to adaptive difficulty
+    /// without a fixed seed.
+    pub fn difficulty(&self) -> Difficulty {
+        if self.format == GameFormat::Survival {
+            let answered = (self.correct_answers + self.wrong_answers) as u32;
+            let level = 1 + answered / self.config.survival_level_every;
+            return Difficulty::at_level(level.min(Difficulty::MAX_LEVEL));
+        }
+        let score = self.score - self.speed_points;
+        difficulty(&self.config, &self.adaptive, self.mode(), score)
+    }
+
+    /// Subtracts the wrong-answer penalty on top of the normal countdown,
+    /// adds it to a sprint's time, or takes a survival life.
+    fn apply_penalty(&mut self) {
+        match self.format {
+            GameFormat::Timed => {
+                self.remaining_time = self
+                    .remaining_time
+                    .saturating_sub(self.config.wrong_penalty());
+            }
+            GameFormat::Sprint => {
+                self.penalty = self.penalty.saturating_add(self.config.wrong_penalty())
+            }
+            GameFormat::Survival => self.lives = self.lives.saturating_sub(1),
+        }
+    }
+
+    fn end(&mut self) {
+        self.game_over = true;
+        self.last_tick = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.last_tick.is_some()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn problem(&self) -> &Problem {
+        &self.problem
+    }
+
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+    pub fn correct_answers(&self) -> i32 {
+        self.correct_answers
+    }
+
+    pub fn wrong_answers(&self) -> i32 {
+        self.wrong_answers
+    }
+
+    /// The options to pick from, or an empty slice when answers are typed.
+    pub fn choices(&self) -> &[Answer] {
+        &self.choices
+    }
+
+    /// Time played plus sprint penalties: the result of a sprint.
+    pub fn finish_time(&self) -> Duration {
+        self.elapsed + self.penalty
+    }
+
+    /// Correct answers still needed to finish a sprint.
+    pub fn remaining_questions(&self) -> Option<u32> {
+        (self.format == GameFormat::Sprint)
+            .then(|| (self.config.sprint_questions as i32 - self.correct_answers).max(0) as u32)
+    }
+
+    pub fn streak(&self) -> u32 {
+        self.streak
+    }
+
+    /// What the next correct answer's points are multiplied by.
+    pub fn multiplier(&self) -> i32 {
+        self.config.streak_multiplier(self.streak)
+    }
+
+    /// Time the current question gets in full, given the streak.
+    pub fn question_time(&self) -> Duration {
+        self.config.question_time(self.streak)
+    }
+
+    /// Time left on the current question, when questions are timed.
+    pub fn question_time_left(&self) -> Option<Duration> {
+        self.question_time_left
+    }
+
+    /// How long a front end may wait for input before a countdown runs
+    /// out, or `None` when nothing is counting down.
+    pub fn time_until_timeout(&self) -> Option<Duration> {
+        let clock = self.format.has_clock().then_some(self.remaining_time);
+        match (clock, self.question_time_left) {
+            (Some(clock), Some(question)) => Some(clock.min(question)),
+            (clock, question) => clock.or(question),
+        }
+    }
+
+    /// Lives left, in survival.
+    pub fn lives(&self) -> Option<u32> {
+        (self.format == GameFormat::Survival).then_some(self.lives)
+    }
+
+    pub fn remaining_time(&self) -> Duration {
+        self.remaining_time
+    }
+
+    /// Every answer submitted so far, in order.
+    pub fn history(&self) -> &[QuestionRecord] {
+        &self.history
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn format(&self) -> GameFormat {
+        self.format
+    }
+
+    pub fn mode(&self) -> PracticeMode {
+        self.generator.mode()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.generator.seed()
+    }
+}
+
+fn difficulty(
+    config: &GameConfig,
+    adaptive: &AdaptiveDifficulty,
+    mode: PracticeMode,
+    score: i32,
+) -> Difficulty {
+    if config.adaptive {
+        adaptive.difficulty(mode)
+    } else {
+        config.difficulty_for(score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started_session() -> (GameSession, Instant) {
+        let mut session = GameSession::new(GameConfig::default(), ProblemGenerator::new(7));
+        let now = Instant::now();
+        session.start(now);
+        (session, now)
+    }
+
+    #[test]
+    fn correct_answer_scores_and_adds_time() {
+        let (mut session, _) = started_session();
+        let answer = session.problem().answer.to_string();
+        assert_eq!(session.submit(&answer), Some(SubmitOutcome::Correct));
+        // One point plus the speed bonus for answering instantly
+        assert_eq!(session.score(), 2);
+        assert_eq!(session.remaining_time(), Duration::from_secs(31));
+    }
+
+    #[test]
+    fn streaks_multiply_points_and_fast_answers_earn_a_bonus() {
+        let config = GameConfig {
+            starting_secs: 600,
+            ..GameConfig::default()
+        };
+        let mut session = GameSession::new(
+            config,
+            ProblemGenerator::new(3).with_mode(PracticeMode::Addition),
+        );
+        let mut now = Instant::now();
+        session.start(now);
+        for _ in 0..5 {
+            now += Duration::from_secs(3);
+            session.tick(now);
+            let answer = session.problem().answer.to_string();
+            session.submit(&answer);
+        }
+        assert_eq!((session.streak(), session.multiplier()), (5, 2));
+        assert_eq!(session.score(), 5);
+
+        now += Duration::from_secs(1);
+        session.tick(now);
+        let answer = session.problem().answer.to_string();
+        session.submit(&answer);
+        assert_eq!(session.score(), 5 + 2 + 1);
+
+        session.submit("nope");
+        assert_eq!((session.streak(), session.multiplier()), (0, 1));
+    }
+
+    #[test]
+    fn wrong_and_invalid_answers_cost_time() {
+        let (mut session, _) = started_session();
+        let expected = session.problem().answer.clone();
+        let wrong = expected.value().numer() + 1;
+        assert_eq!(
+            session.submit(&wrong.to_string()),
+            Some(SubmitOutcome::Wrong { expected })
+        );
+        assert_eq!(session.submit("abc"), Some(SubmitOutcome::Invalid));
+        assert_eq!(session.wrong_answers(), 2);
+        assert_eq!(session.remaining_time(), Duration::from_secs(26));
+    }
+
+    #[test]
+    fn answers_are_logged_with_their_latency() {
+        let (mut session, now) = started_session();
+        let answer = session.problem().answer.to_string();
+        session.tick(now + Duration::from_secs(3));
+        session.submit(&answer);
+        session.tick(now + Duration::from_secs(4));
+        session.submit("nope");
+
+        let history = session.history();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].correct);
+        assert_eq!(history[0].latency, Duration::from_secs(3));
+        assert_eq!(history[1].given, "nope");
+        assert!(!history[1].correct);
+        assert_eq!(history[1].latency, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn strong_players_see_pemdas_early_in_mixed_play() {
+        for seed in 0..50 {
+            let mut session = GameSession::new(GameConfig::default(), ProblemGenerator::new(seed));
+            let mut now = Instant::now();
+            session.start(now);
+            let answered = (0..100)
+                .position(|_| {
+                    if session.problem().is_pemdas() {
+                        return true;
+                    }
+                    now += Duration::from_secs(1);
+                    session.tick(now);
+                    let answer = session.problem().answer.to_string();
+                    session.submit(&answer);
+                    false
+                })
+                .unwrap();
+            assert!(
+                answered <= 40,
+                "seed {}: first PEMDAS after {}",
+                seed,
+                answered
+            );
+        }
+    }
+
+    #[test]
+    fn same_seed_asks_the_same_questions_at_any_speed() {
+        let questions = |latency: Duration| {
+            let generator = ProblemGenerator::new(42).with_mode(PracticeMode::TimesTables);
+            let config = GameConfig {
+                starting_secs: 3600,
+                ..GameConfig::default()
+            };
+            let mut session = GameSession::new(config, generator);
+            let mut now = Instant::now();
+            session.start(now);
+            let mut asked = Vec::new();
+            for _ in 0..30 {
+                asked.push(session.problem().question.clone());
+                now += latency;
+                session.tick(now);
+                let answer = session.problem().answer.to_string();
+                session.submit(&answer);
+            }
+            asked
+        };
+        assert_eq!(
+            questions(Duration::from_secs(1)),
+            questions(Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn multiple_choice_offers_the_answer() {
+        let config = GameConfig {
+            multiple_choice: true,
+            ..GameConfig::default()
+        };
+        let mut session = GameSession::new(config, ProblemGenerator::new(7));
+        session.start(Instant::now());
+        let expected = session.problem().answer.clone();
+        let index = session.choices().iter().position(|c| *c == expected);
+        assert_eq!(
+            session.submit_choice(index.unwrap()),
+            Some(SubmitOutcome::Correct)
+        );
+        assert_eq!(session.choices().len(), choices::CHOICE_COUNT);
+        assert_eq!(session.submit_choice(choices::CHOICE_COUNT), None);
+    }
+
+    #[test]
+    fn sprint_ends_after_enough_correct_answers() {
+        let config = GameConfig {
+            sprint_questions: 2,
+            ..GameConfig::default()
+        };
+        let mut session =
+            GameSession::new(config, ProblemGenerator::new(7)).with_format(GameFormat::Sprint);
+        let now = Instant::now();
+        session.start(now);
+        session.tick(now + Duration::from_secs(60));
+        assert!(!session.is_over());
+
+        session.submit("nope");
+        for _ in 0..2 {
+            let answer = session.problem().answer.to_string();
+            session.submit(&answer);
+        }
+        assert!(session.is_over());
+        assert_eq!(session.remaining_questions(), Some(0));
+        assert_eq!(session.finish_time(), Duration::from_secs(62));
+    }
+
+    #[test]
+    fn survival_ends_when_lives_run_out() {
+        let config = GameConfig {
+            speed_bonus_points: 0,
+            streak_tiers: Vec::new(),
+            ..GameConfig::default()
+        };
+        let mut session =
+            GameSession::new(config, ProblemGenerator::new(7)).with_format(GameFormat::Survival);
+        let now = Instant::now();
+        session.start(now);
+        session.tick(now + Duration::from_secs(600));
+        assert_eq!(session.lives(), Some(3));
+
+        for _ in 0..10 {
+            let answer = session.problem().answer.to_string();
+            session.submit(&answer);
+        }
+        assert_eq!(session.difficulty().level, 3);
+        session.submit("nope");
+        session.submit("nope");
+        assert!(!session.is_over());
+        session.submit("nope");
+        assert_eq!(session.lives(), Some(0));
+        assert!(session.is_over());
+        assert_eq!(session.score(), 10);
+    }
+
+    #[test]
+    fn timed_out_questions_count_as_wrong() {
+        let config = GameConfig {
+            question_timer: true,
+            ..GameConfig::default()
+        };
+        let mut session =
+            GameSession::new(config, ProblemGenerator::new(7)).with_format(GameFormat::Survival);
+        let now = Instant::now();
+        session.start(now);
+        let answer = session.problem().answer.to_string();
+        session.tick(now + Duration::from_secs(1));
+        session.submit(&answer);
+        assert_eq!(session.streak(), 1);
+        assert_eq!(
+            session.question_time_left(),
+            Some(Duration::from_millis(4750))
+        );
+
+        let outcome = session.tick(now + Duration::from_secs(6));
+        assert!(matches!(outcome, Some(SubmitOutcome::TimedOut { .. })));
+        assert_eq!((session.wrong_answers(), session.streak()), (1, 0));
+        assert_eq!(session.history()[1].latency, Duration::from_millis(4750));
+
+        // Two more full questions run out within one long tick
+        session.tick(now + Duration::from_millis(15_750));
+        assert!(session.is_over());
+        assert_eq!(session.wrong_answers(), 3);
+    }
+
+    #[test]
+    fn game_ends_when_time_runs_out() {
+        let (mut session, now) = started_session();
+        session.tick(now + Duration::from_secs(10));
+        assert_eq!(session.remaining_time(), Duration::from_secs(20));
+        session.tick(now + Duration::from_secs(31));
+        assert!(session.is_over());
+        assert_eq!(session.submit("1"), None);
+    }
+}
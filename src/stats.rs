@@ -0,0 +1,116 @@
+use crate::generator::ProblemKind;
+use crate::session::QuestionRecord;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// How many of the slowest answers the summary keeps.
+const SLOWEST_COUNT: usize = 3;
+
+/// Correct and total answers for one kind of problem.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Accuracy {
+    pub correct: usize,
+    pub total: usize,
+}
+
+impl Accuracy {
+    /// Share of correct answers, from 0.0 to 1.0.
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.correct as f64 / self.total as f64
+        }
+    }
+}
+
+/// A post-game summary of a session's answer history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionStats {
+    pub answered: usize,
+    pub average_time: Duration,
+    pub median_time: Duration,
+    /// The slowest answers, slowest first.
+    pub slowest: Vec<QuestionRecord>,
+    pub accuracy_by_kind: BTreeMap<ProblemKind, Accuracy>,
+}
+
+impl SessionStats {
+    /// Summarizes `history`, or returns `None` if nothing was answered.
+    pub fn from_history(history: &[QuestionRecord]) -> Option<Self> {
+        if history.is_empty() {
+            return None;
+        }
+
+        let mut latencies: Vec<Duration> = history.iter().map(|r| r.latency).collect();
+        latencies.sort();
+        let total: Duration = latencies.iter().sum();
+        let average_time = total / latencies.len() as u32;
+        let middle = latencies.len() / 2;
+        let median_time = if latencies.len().is_multiple_of(2) {
+            (latencies[middle - 1] + latencies[middle]) / 2
+        } else {
+            latencies[middle]
+        };
+
+        let mut slowest = history.to_vec();
+        slowest.sort_by_key(|r| std::cmp::Reverse(r.latency));
+        slowest.truncate(SLOWEST_COUNT);
+
+        let mut accuracy_by_kind = BTreeMap::<ProblemKind, Accuracy>::new();
+        for record in history {
+            let accuracy = accuracy_by_kind.entry(record.kind).or_default();
+            accuracy.total += 1;
+            if record.correct {
+                accuracy.correct += 1;
+            }
+        }
+
+        Some(Self {
+            answered: history.len(),
+            average_time,
+            median_time,
+            slowest,
+            accuracy_by_kind,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer::Answer;
+
+    fn record(kind: ProblemKind, secs: u64, correct: bool) -> QuestionRecord {
+        QuestionRecord {
+            question: format!("{} s", secs),
+            expected: Answer::Integer(0),
+            given: "0".to_string(),
+            correct,
+            latency: Duration::from_secs(secs),
+            kind,
+        }
+    }
+
+    #[test]
+    fn summarizes_times_and_accuracy() {
+        let history = [
+            record(ProblemKind::Addition, 1, true),
+            record(ProblemKind::Addition, 4, false),
+            record(ProblemKind::Pemdas, 6, true),
+            record(ProblemKind::Division, 3, true),
+        ];
+        let stats = SessionStats::from_history(&history).unwrap();
+        assert_eq!(stats.average_time, Duration::from_millis(3500));
+        assert_eq!(stats.median_time, Duration::from_millis(3500));
+        assert_eq!(stats.slowest[0].question, "6 s");
+        assert_eq!(
+            stats.accuracy_by_kind[&ProblemKind::Addition],
+            Accuracy {
+                correct: 1,
+                total: 2
+            }
+        );
+        assert!(SessionStats::from_history(&[]).is_none());
+    }
+}
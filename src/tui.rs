@@ -0,0 +1,226 @@
+//! Line-based terminal front end, for machines where no window can be opened.
+
+use rapid_math::export::{ExportFormat, SessionExport};
+use rapid_math::highscores::{HighScores, ScoreEntry, LEADERBOARD_SIZE};
+use rapid_math::session::{GameFormat, GameSession, SubmitOutcome};
+use rapid_math::stats::SessionStats;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Instant;
+
+pub fn run(mut session: GameSession, export_path: Option<PathBuf>) -> io::Result<()> {
+    // Read stdin on its own thread so the countdown can end the game while
+    // the player is still typing.
+    let (lines_tx, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            if lines_tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    println!(
+        "Math Quiz: {} ({})",
+        session.mode().label(),
+        session.format().label()
+    );
+    println!("Press Enter to start!");
+    if lines.recv().is_err() {
+        return Ok(());
+    }
+    session.start(Instant::now());
+    println!("Solve the problems!");
+
+    while !session.is_over() {
+        println!();
+        let progress = match session.remaining_questions() {
+            Some(left) => format!(
+                "{:.1}s | {} to go",
+                session.finish_time().as_secs_f64(),
+                left
+            ),
+            None => {
+                let limit = match session.lives() {
+                    Some(lives) => format!("lives {}", lives),
+                    None => format!("{}s left", session.remaining_time().as_secs()),
+                };
+                format!(
+                    "{} | score {} | streak {} x{}",
+                    limit,
+                    session.score(),
+                    session.streak(),
+                    session.multiplier()
+                )
+            }
+        };
+        let progress = match session.question_time_left() {
+            Some(left) => format!("{} | {:.1}s to answer", progress, left.as_secs_f64()),
+            None => progress,
+        };
+        println!(
+            "[{} | level {}] {}",
+            progress,
+            session.difficulty().level,
+            session.problem().question
+        );
+        let choices: Vec<String> = (session.choices().iter().enumerate())
+            .map(|(index, choice)| format!("{}) {}", index + 1, choice))
+            .collect();
+        if !choices.is_empty() {
+            println!("{}", choices.join("   "));
+        }
+        print!("> ");
+        io::stdout().flush()?;
+
+        // Without a countdown there is nothing to wake up for
+        let line = match session.time_until_timeout() {
+            Some(timeout) => lines.recv_timeout(timeout),
+            None => lines.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let line = match line {
+            Ok(line) => line?,
+            Err(RecvTimeoutError::Timeout) => {
+                print_outcome(session.tick(Instant::now()));
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        // A line that arrives after its question ran out is dropped
+        let timed_out = session.tick(Instant::now());
+        if timed_out.is_some() {
+            print_outcome(timed_out);
+            continue;
+        }
+        if session.is_over() {
+            break;
+        }
+
+        // In multiple-choice play a number picks an option
+        let picked = line
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|&n| (1..=choices.len()).contains(&n));
+        let outcome = match picked {
+            Some(n) => session.submit_choice(n - 1),
+            None => session.submit(&line),
+        };
+        print_outcome(outcome);
+    }
+
+    println!();
+    println!("Game Over");
+    if session.format() == GameFormat::Sprint {
+        println!("Finish Time: {:.1} s", session.finish_time().as_secs_f64());
+    } else {
+        println!("Final Score: {}", session.score());
+    }
+    println!("Correct Answers: {}", session.correct_answers());
+    println!("Wrong Answers: {}", session.wrong_answers());
+    println!("Seed: {}", session.seed());
+    print_stats(&session);
+
+    if let Some(path) = export_path {
+        let format = ExportFormat::from_path(&path).unwrap_or(ExportFormat::Csv);
+        match SessionExport::from_session(&session).write(&path, format) {
+            Ok(()) => println!("Exported to {}", path.display()),
+            Err(err) => println!("Could not export to {}: {}", path.display(), err),
+        }
+    }
+
+    // Input ran out before the game ended. An abandoned game's result
+    // would distort the leaderboard, and a sprint's time would beat every
+    // finished one
+    if !session.is_over() {
+        println!("Game not finished, so no score was recorded");
+        return Ok(());
+    }
+
+    if let Err(message) = crate::save_review(&session) {
+        println!("{}", message);
+    }
+    let Some(path) = HighScores::default_path(session.format()) else {
+        println!("No data directory to save high scores in");
+        return Ok(());
+    };
+    let entry = ScoreEntry::from_session(&session);
+    match HighScores::record_at(&path, entry) {
+        Ok((scores, placement)) => {
+            if placement.personal_best {
+                println!();
+                println!("New personal best!");
+            }
+            println!();
+            match placement.questions {
+                Some(questions) => println!("High Scores (sprints of {} questions)", questions),
+                None => println!("High Scores"),
+            }
+            for (rank, entry) in scores
+                .top(placement.questions, LEADERBOARD_SIZE)
+                .into_iter()
+                .enumerate()
+            {
+                let marker = if placement.rank == Some(rank) {
+                    '*'
+                } else {
+                    ' '
+                };
+                let result = match entry.time_ms {
+                    Some(time_ms) => format!("{:.1}s", time_ms as f64 / 1000.0),
+                    None => entry.score.to_string(),
+                };
+                println!(
+                    "{}{:>3}. {:>6}  {:>3} correct  {:>3} wrong  {}  {}",
+                    marker,
+                    rank + 1,
+                    result,
+                    entry.correct_answers,
+                    entry.wrong_answers,
+                    entry.date.format("%Y-%m-%d"),
+                    entry.mode
+                );
+            }
+        }
+        Err(err) => println!("Could not save high scores: {}", err),
+    }
+    Ok(())
+}
+
+fn print_outcome(outcome: Option<SubmitOutcome>) {
+    if let Some(outcome) = outcome {
+        println!("{}", outcome);
+    }
+}
+
+fn print_stats(session: &GameSession) {
+    let Some(stats) = SessionStats::from_history(session.history()) else {
+        return;
+    };
+
+    println!();
+    println!("Statistics");
+    println!("Average time: {:.1} s", stats.average_time.as_secs_f64());
+    println!("Median time: {:.1} s", stats.median_time.as_secs_f64());
+    println!("Slowest questions:");
+    for record in &stats.slowest {
+        println!(
+            "  {} → {}  ({:.1} s)",
+            record.question,
+            record.expected,
+            record.latency.as_secs_f64()
+        );
+    }
+    println!("Accuracy by operation:");
+    for (kind, accuracy) in &stats.accuracy_by_kind {
+        println!(
+            "  {:<15} {}/{}  {:.0}%",
+            kind.label(),
+            accuracy.correct,
+            accuracy.total,
+            accuracy.ratio() * 100.0
+        );
+    }
+}